@@ -0,0 +1,214 @@
+use {
+  image::DynamicImage,
+  sha2::{
+    Digest as _,
+    Sha256
+  },
+  std::{
+    collections::HashMap,
+    hash::{
+      Hash,
+      Hasher
+    },
+    time::{
+      Duration,
+      Instant
+    }
+  }
+};
+
+/// Least recently used cache bounded by the total weight of its values, usually their size in bytes<br>
+/// Entries older than the optional TTL are dropped when looked up
+pub(crate) struct LruCache<K, V> {
+  entries:   HashMap<K, Entry<V>>,
+  capacity:  usize,
+  weight:    usize,
+  tick:      u64,
+  ttl:       Option<Duration>,
+  evictions: u64
+}
+
+struct Entry<V> {
+  value:     V,
+  weight:    usize,
+  last_used: u64,
+  inserted:  Instant
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+  pub(crate) fn new(capacity: usize) -> Self {
+    Self {
+      entries: HashMap::new(),
+      capacity,
+      weight: 0,
+      tick: 0,
+      ttl: None,
+      evictions: 0
+    }
+  }
+
+  pub(crate) fn get(
+    &mut self,
+    key: &K
+  ) -> Option<V> {
+    if !self.contains(key) {
+      if self.remove(key).is_some() {
+        self.evictions += 1;
+      }
+      return None
+    }
+
+    self.tick += 1;
+    let entry = self.entries.get_mut(key)?;
+    entry.last_used = self.tick;
+    Some(entry.value.clone())
+  }
+
+  /// Whether a fresh entry exists, without marking it as used
+  pub(crate) fn contains(
+    &self,
+    key: &K
+  ) -> bool {
+    self
+      .entries
+      .get(key)
+      .is_some_and(|e| self.ttl.is_none_or(|ttl| e.inserted.elapsed() < ttl))
+  }
+
+  /// Stores the value, evicting the least recently used entries until it fits<br>
+  /// Values heavier than the whole capacity aren't stored
+  pub(crate) fn insert(
+    &mut self,
+    key: K,
+    value: V,
+    weight: usize
+  ) {
+    self.remove(&key);
+    if weight > self.capacity {
+      return
+    }
+
+    self.tick += 1;
+    self.weight += weight;
+    self.entries.insert(
+      key,
+      Entry {
+        value,
+        weight,
+        last_used: self.tick,
+        inserted: Instant::now()
+      }
+    );
+    self.shrink();
+  }
+
+  pub(crate) fn remove(
+    &mut self,
+    key: &K
+  ) -> Option<V> {
+    let entry = self.entries.remove(key)?;
+    self.weight -= entry.weight;
+    Some(entry.value)
+  }
+
+  pub(crate) fn set_capacity(
+    &mut self,
+    capacity: usize
+  ) {
+    self.capacity = capacity;
+    self.shrink();
+  }
+
+  pub(crate) fn set_ttl(
+    &mut self,
+    ttl: Option<Duration>
+  ) {
+    self.ttl = ttl;
+  }
+
+  pub(crate) fn len(&self) -> usize { self.entries.len() }
+
+  /// Total weight of the stored values
+  pub(crate) fn weight(&self) -> usize { self.weight }
+
+  /// Entries dropped so far to make room or because they expired
+  pub(crate) fn evictions(&self) -> u64 { self.evictions }
+
+  pub(crate) fn clear(&mut self) {
+    self.entries.clear();
+    self.weight = 0;
+  }
+
+  fn shrink(&mut self) {
+    while self.weight > self.capacity {
+      let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) else {
+        break
+      };
+      self.remove(&oldest);
+      self.evictions += 1;
+    }
+  }
+}
+
+/// SHA-256 of everything the value feeds into a [Hasher]<br>
+/// Used for keys of content that may come from users, who could otherwise pick colliding inputs
+pub(crate) fn digest(value: &impl Hash) -> [u8; 32] {
+  let mut hasher = DigestHasher(Sha256::new());
+  value.hash(&mut hasher);
+  hasher.0.finalize().into()
+}
+
+struct DigestHasher(Sha256);
+
+impl Hasher for DigestHasher {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) {
+    self.0.update(bytes);
+  }
+
+  fn finish(&self) -> u64 { u64::from_le_bytes(self.0.clone().finalize()[..8].try_into().unwrap()) }
+}
+
+/// Feeds the image's dimensions and pixels into the hasher
+pub(crate) fn hash_image<H: Hasher>(
+  image: &DynamicImage,
+  state: &mut H
+) {
+  std::mem::discriminant(image).hash(state);
+  (image.width(), image.height()).hash(state);
+  image.as_bytes().hash(state);
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_evicts_least_recently_used() {
+    let mut cache = LruCache::new(10);
+    cache.insert("a", 1, 4);
+    cache.insert("b", 2, 4);
+    cache.get(&"a");
+    cache.insert("c", 3, 4);
+
+    assert_eq!(cache.get(&"a"), Some(1));
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"c"), Some(3));
+
+    cache.insert("huge", 4, 11);
+    assert_eq!(cache.get(&"huge"), None);
+    assert_eq!(cache.evictions(), 1);
+  }
+
+  #[test]
+  fn test_expires_after_ttl() {
+    let mut cache = LruCache::new(10);
+    cache.set_ttl(Some(Duration::ZERO));
+    cache.insert("a", 1, 1);
+
+    assert_eq!(cache.get(&"a"), None);
+    assert_eq!((cache.len(), cache.evictions()), (0, 1));
+  }
+}
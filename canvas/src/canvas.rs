@@ -1,11 +1,29 @@
 use {
-  crate::layer::Layer,
+  crate::{
+    cache::{
+      LruCache,
+      digest
+    },
+    fill::Fill,
+    layer::{
+      Layer,
+      View
+    },
+    raster::{
+      Prepared,
+      Surface
+    }
+  },
   ab_glyph::{
     Font,
     FontArc,
     PxScale,
     ScaleFont
   },
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
+  },
   image::{
     DynamicImage,
     ExtendedColorType,
@@ -19,17 +37,38 @@ use {
     }
   },
   lazy_static::lazy_static,
+  rayon::prelude::*,
   regex::Regex,
-  reqwest::Client,
-  std::io::Cursor,
+  serde::{
+    Deserialize,
+    Serialize
+  },
+  std::{
+    hash::{
+      Hash,
+      Hasher
+    },
+    io::Cursor,
+    sync::{
+      LazyLock,
+      Mutex
+    }
+  },
   unicode_segmentation::UnicodeSegmentation
 };
 
 lazy_static! {
-  static ref DISCORD_EMOTE_REGEX: Regex = Regex::new(r"<a?:\w+:(\d+)>").expect("regex pattern failed");
+  /// Discord emotes like `<:name:id>` and emoji shortcodes like `:floppy_disk:`
+  static ref EMOTE_TOKEN_REGEX: Regex = Regex::new(r"<a?:\w+:(\d+)>|:([a-z0-9_+\-]+):").expect("regex pattern failed");
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+/// Canvases with at least this many pixels are rendered in parallel
+const PARALLEL_MIN_PIXELS: u64 = 512 * 512;
+
+/// Encoded images keyed by the SHA-256 digest of the canvas and output format
+static RENDER_CACHE: LazyLock<Mutex<LruCache<[u8; 32], Vec<u8>>>> = LazyLock::new(|| Mutex::new(LruCache::new(32 * 1024 * 1024)));
+
+#[derive(Default, Debug, Clone, Copy, Hash)]
 pub enum ImageFormat {
   #[default]
   WebP,
@@ -38,19 +77,54 @@ pub enum ImageFormat {
   }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EmoteSource {
+  /// Custom emote by its ID
   Discord(String),
-  Unicode(char)
+  /// Whole emoji grapheme, e.g. `👨‍👩‍👧` or `🇯🇵`
+  Unicode(String)
+}
+
+impl EmoteSource {
+  /// Key identifying the emote in the emote cache
+  pub(crate) fn cache_key(&self) -> String {
+    match self {
+      EmoteSource::Discord(id) => id.clone(),
+      EmoteSource::Unicode(emoji) => format!("twemoji_{}", twemoji_name(emoji))
+    }
+  }
+}
+
+/// Twemoji file name of the emoji, its codepoints in lowercase hex joined by `-`<br>
+/// U+FE0F is dropped unless the emoji is a ZWJ sequence, matching how Twemoji names its assets
+pub(crate) fn twemoji_name(emoji: &str) -> String {
+  let emoji = emojis::get(emoji).map_or(emoji, |e| e.as_str());
+  let keep_fe0f = emoji.contains('\u{200d}');
+  emoji
+    .chars()
+    .filter(|&c| keep_fe0f || c != '\u{fe0f}')
+    .map(|c| format!("{:x}", c as u32))
+    .collect::<Vec<_>>()
+    .join("-")
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Canvas {
-  pub width:    u32,
-  pub height:   u32,
-  pub bg_color: Rgba<u8>,
-  pub layers:   Vec<Layer>
+  pub width:      u32,
+  pub height:     u32,
+  #[serde(default = "default_background")]
+  pub background: Fill,
+  #[serde(default)]
+  pub layers:     Vec<Layer>,
+  /// Pixel density of the output, see [Canvas::with_scale]
+  #[serde(default = "default_scale")]
+  pub scale:      f32
 }
 
+fn default_background() -> Fill { Fill::Solid(Rgba([0, 0, 0, 255])) }
+
+fn default_scale() -> f32 { 1.0 }
+
 impl Canvas {
   pub fn new(
     width: u32,
@@ -59,16 +133,43 @@ impl Canvas {
     Self {
       width,
       height,
-      bg_color: Rgba([0, 0, 0, 255]),
-      layers: Vec::new()
+      background: default_background(),
+      layers: Vec::new(),
+      scale: default_scale()
     }
   }
 
+  /// Renders everything `scale` times larger for high-DPI screens, e.g. `2.0` for a 2x image<br>
+  /// Layers keep their coordinates, positions, sizes, font sizes, line widths and images are scaled while drawing
+  pub fn with_scale(
+    mut self,
+    scale: f32
+  ) -> Self {
+    self.scale = scale.max(f32::EPSILON);
+    self
+  }
+
+  /// Size of the rendered image in pixels
+  pub fn output_size(&self) -> (u32, u32) {
+    (
+      (self.width as f32 * self.scale).round() as u32,
+      (self.height as f32 * self.scale).round() as u32
+    )
+  }
+
   pub fn set_bg_color(
     &mut self,
     color: Rgba<u8>
   ) {
-    self.bg_color = color;
+    self.background = Fill::Solid(color);
+  }
+
+  /// Replaces the background with a gradient, image or solid color fill
+  pub fn set_background(
+    &mut self,
+    fill: impl Into<Fill>
+  ) {
+    self.background = fill.into();
   }
 
   pub fn add_layer(
@@ -78,35 +179,106 @@ impl Canvas {
     self.layers.push(layer);
   }
 
-  /// Render the Canvas image
+  /// Render the Canvas image<br>
+  /// Large canvases are split into bands of rows rasterized in parallel
   pub fn render(&self) -> DynamicImage {
-    let mut img = RgbaImage::from_pixel(self.width, self.height, self.bg_color);
+    let (width, height) = self.output_size();
+    let mut img = RgbaImage::new(width, height);
+    let threads = rayon::current_num_threads();
 
-    for layer in &self.layers {
-      layer.render(&mut img);
+    if width as u64 * height as u64 >= PARALLEL_MIN_PIXELS && threads > 1 {
+      // resize the images once up front, otherwise every band would redo it
+      let mut prepared: Prepared = self.layers.par_iter().flat_map_iter(|layer| layer.prepare(self.scale)).collect();
+      prepared.extend(self.prepare_background());
+
+      let rows = (height as usize).div_ceil(threads * 4).max(16);
+      img.par_chunks_mut(width as usize * 4 * rows).enumerate().for_each(|(i, band)| {
+        let band_height = (band.len() / (width as usize * 4)) as u32;
+        self.draw(&mut Surface::band(band, width, (i * rows) as i32, band_height, Some(&prepared)));
+      });
+    } else {
+      self.draw(&mut Surface::new(&mut img));
     }
 
     DynamicImage::ImageRgba8(img)
   }
 
-  /// Exports the image into bytes (Vec<u8>) with specified encoder
+  fn prepare_background(&self) -> Option<(usize, RgbaImage)> {
+    let (width, height) = self.output_size();
+    let image = self.background.prepare((width as f32, height as f32))?;
+    Some((&self.background as *const Fill as usize, image))
+  }
+
+  fn draw(
+    &self,
+    surface: &mut Surface
+  ) {
+    let (width, height) = self.output_size();
+    let shader = self.background.shader((0.0, 0.0), (width as f32, height as f32), surface.prepared);
+    surface.paint(&shader);
+
+    for layer in &self.layers {
+      layer.draw(surface, View::scaled(self.scale));
+    }
+  }
+
+  /// Renders on Tokio's blocking thread pool so large canvases don't stall the async runtime
+  pub async fn render_async(self) -> AsahiResult<DynamicImage> {
+    tokio::task::spawn_blocking(move || self.render())
+      .await
+      .map_err(|e| AsahiError::Worker(format!("render task failed: {e}").into()))
+  }
+
+  /// Exports the image into bytes (Vec<u8>) with specified encoder<br>
+  /// Results are kept in a render cache, an identical canvas skips rendering and encoding, see [set_render_cache_size]
   pub fn to_bytes(
     &self,
     format: Option<ImageFormat>
+  ) -> Result<Vec<u8>, ImageError> {
+    let format = format.unwrap_or_default();
+    // canvases can be built from user content, so the key is a cryptographic hash that can't be made to collide
+    let key = digest(&(self, format));
+
+    if let Some(bytes) = RENDER_CACHE.lock().expect("failed to acquire lock").get(&key) {
+      return Ok(bytes)
+    }
+
+    let bytes = self.encode(format)?;
+    RENDER_CACHE
+      .lock()
+      .expect("failed to acquire lock")
+      .insert(key, bytes.clone(), bytes.len());
+    Ok(bytes)
+  }
+
+  /// [Canvas::to_bytes] running on Tokio's blocking thread pool
+  pub async fn to_bytes_async(
+    self,
+    format: Option<ImageFormat>
+  ) -> AsahiResult<Vec<u8>> {
+    tokio::task::spawn_blocking(move || self.to_bytes(format))
+      .await
+      .map_err(|e| AsahiError::Worker(format!("render task failed: {e}").into()))?
+      .map_err(|e| AsahiError::External(format!("failed to encode canvas: {e}").into()))
+  }
+
+  fn encode(
+    &self,
+    format: ImageFormat
   ) -> Result<Vec<u8>, ImageError> {
     let img = self.render();
     let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
     let mut buf = Vec::new();
 
-    let format = format.unwrap_or_default();
     match format {
       ImageFormat::WebP => {
         let encoder = WebPEncoder::new_lossless(Cursor::new(&mut buf));
-        encoder.write_image(&rgba, self.width, self.height, ExtendedColorType::Rgba8)?;
+        encoder.write_image(&rgba, width, height, ExtendedColorType::Rgba8)?;
       },
       ImageFormat::Jpeg { quality } => {
         let encoder = JpegEncoder::new_with_quality(Cursor::new(&mut buf), quality);
-        encoder.write_image(&img.to_rgb8(), self.width, self.height, ExtendedColorType::Rgb8)?;
+        encoder.write_image(&img.to_rgb8(), width, height, ExtendedColorType::Rgb8)?;
       }
     }
 
@@ -114,6 +286,22 @@ impl Canvas {
   }
 }
 
+impl Hash for Canvas {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    (self.width, self.height, &self.background, &self.layers, self.scale.to_bits()).hash(state);
+  }
+}
+
+/// Sets how many bytes of encoded images [Canvas::to_bytes] keeps around, least recently used ones are dropped first<br>
+/// Defaults to 32 MiB, `0` disables the cache
+pub fn set_render_cache_size(bytes: usize) { RENDER_CACHE.lock().expect("failed to acquire lock").set_capacity(bytes); }
+
+/// Drops every cached render
+pub fn clear_render_cache() { RENDER_CACHE.lock().expect("failed to acquire lock").clear(); }
+
 /// Converts the value into [Rgba] format
 pub fn to_rgba(color: u32) -> Rgba<u8> {
   let r = ((color >> 16) & 0xFF) as u8;
@@ -132,59 +320,243 @@ pub(crate) fn assume_text_width(
   text.chars().map(|c| scaled.h_advance(scaled.glyph_id(c))).sum::<f32>().ceil() as u32
 }
 
-pub fn parse_all_emotes(s: &str) -> Vec<EmoteSource> {
+/// Shortens the text with an ellipsis so its assumed width fits within `max_width`
+pub(crate) fn truncate_text(
+  text: &str,
+  font_size: f32,
+  font: &FontArc,
+  max_width: u32
+) -> String {
+  if assume_text_width(text, font_size, font) <= max_width {
+    return text.to_string()
+  }
+
+  let budget = max_width.saturating_sub(assume_text_width("…", font_size, font));
+  let mut out = String::new();
+  for grapheme in text.graphemes(true) {
+    out.push_str(grapheme);
+    if assume_text_width(&out, font_size, font) > budget {
+      out.truncate(out.len() - grapheme.len());
+      break
+    }
+  }
+  format!("{}…", out.trim_end())
+}
+
+/// Emotes returned by [parse_all_emotes]
+pub const DEFAULT_EMOTE_LIMIT: usize = 3;
+
+/// First [DEFAULT_EMOTE_LIMIT] Discord emotes, emojis and `:shortcode:`s in the text, in order of appearance<br>
+/// Use [parse_emotes] with `None` to get every one of them
+pub fn parse_all_emotes(s: &str) -> Vec<EmoteSource> { parse_emotes(s, Some(DEFAULT_EMOTE_LIMIT)) }
+
+/// Emotes in the text like [parse_all_emotes], stops after `limit` emotes when given<br>
+/// Emojis are kept whole, so ZWJ sequences, flags, keycaps and skin tones make a single emote
+pub fn parse_emotes(
+  s: &str,
+  limit: Option<usize>
+) -> Vec<EmoteSource> {
+  split_emotes(s)
+    .into_iter()
+    .filter_map(|segment| match segment {
+      Segment::Emote(emote, _) => Some(emote),
+      Segment::Text(_) => None
+    })
+    .take(limit.unwrap_or(usize::MAX))
+    .collect()
+}
+
+/// Piece of text split by [split_emotes]
+#[derive(Debug, PartialEq)]
+pub(crate) enum Segment<'a> {
+  Text(&'a str),
+  /// Emote along with the markup it was written as
+  Emote(EmoteSource, &'a str)
+}
+
+/// Splits the text into runs of plain text and the emotes between them
+pub(crate) fn split_emotes(s: &str) -> Vec<Segment<'_>> {
   let mut out = Vec::new();
-  let mut remaining = s;
-
-  while !remaining.is_empty() {
-    if let Some(mat) = DISCORD_EMOTE_REGEX.find(remaining) {
-      for ch in remaining[..mat.start()].graphemes(true) {
-        if ch.chars().next().unwrap().len_utf8() >= 3 {
-          out.push(EmoteSource::Unicode(ch.chars().next().unwrap()));
-          if out.len() == 3 {
-            return out;
-          }
-        }
-      }
+  let mut text_start = 0;
+  let mut pos = 0;
 
-      let caps = DISCORD_EMOTE_REGEX.captures(mat.as_str()).unwrap();
-      let id = caps.get(1).unwrap().as_str().to_string();
-      out.push(EmoteSource::Discord(id));
-      if out.len() == 3 {
-        return out;
-      }
+  while let Some(caps) = EMOTE_TOKEN_REGEX.captures_at(s, pos) {
+    let mat = caps.get(0).unwrap();
+    let emote = match (caps.get(1), caps.get(2)) {
+      (Some(id), _) => Some(EmoteSource::Discord(id.as_str().to_string())),
+      (_, Some(code)) => emojis::get_by_shortcode(code.as_str()).map(|e| EmoteSource::Unicode(e.as_str().to_string())),
+      _ => None
+    };
 
-      remaining = &remaining[mat.end()..];
-    } else {
-      for ch in remaining.graphemes(true) {
-        if ch.chars().next().unwrap().len_utf8() >= 3 {
-          out.push(EmoteSource::Unicode(ch.chars().next().unwrap()));
-          if out.len() == 3 {
-            return out;
-          }
-        }
-      }
-      break;
-    }
+    let Some(emote) = emote else {
+      // Unknown shortcode, its closing colon may still open a valid one
+      pos = mat.start() + 1;
+      continue
+    };
+    split_emojis(&s[text_start..mat.start()], &mut out);
+    out.push(Segment::Emote(emote, mat.as_str()));
+    text_start = mat.end();
+    pos = mat.end();
   }
 
+  split_emojis(&s[text_start..], &mut out);
   out
 }
 
-async fn reqwest_img(url: &str) -> Option<DynamicImage> {
-  let http = Client::new();
-  let resp = http.get(url).send().await.ok()?.bytes().await.ok()?;
-  image::load_from_memory(&resp).ok()
+fn split_emojis<'a>(
+  text: &'a str,
+  out: &mut Vec<Segment<'a>>
+) {
+  let mut run_start = 0;
+  for (i, grapheme) in text.grapheme_indices(true) {
+    if let Some(emoji) = classify_emoji(grapheme) {
+      if run_start < i {
+        out.push(Segment::Text(&text[run_start..i]));
+      }
+      out.push(Segment::Emote(EmoteSource::Unicode(emoji.as_str().to_string()), grapheme));
+      run_start = i + grapheme.len();
+    }
+  }
+  if run_start < text.len() {
+    out.push(Segment::Text(&text[run_start..]));
+  }
 }
 
-pub(crate) async fn fetch_discord_emote(id: &str) -> Option<DynamicImage> {
-  let url = format!("https://cdn.discordapp.com/emojis/{id}.webp?size=96");
-  reqwest_img(&url).await
+/// Looks the grapheme up in the Unicode emoji list<br>
+/// Symbols that default to text presentation like `©` or `↔` only count when followed by U+FE0F
+fn classify_emoji(grapheme: &str) -> Option<&'static emojis::Emoji> {
+  let emoji = emojis::get(grapheme)?;
+  let mut chars = grapheme.chars();
+  match (chars.next(), chars.next()) {
+    (Some(ch), None) if emoji.as_str() != grapheme && (ch as u32) < 0x1F000 => None,
+    _ => Some(emoji)
+  }
 }
 
-pub(crate) async fn fetch_twemoji_emote(c: char) -> Option<DynamicImage> {
-  let cpt = format!("{:x}", c as u32);
-  let version = "16.0.1";
-  let url = format!("https://cdnjs.cloudflare.com/ajax/libs/twemoji/{version}/72x72/{cpt}.png");
-  reqwest_img(&url).await
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::{
+      BlendMode,
+      ImageTransform
+    }
+  };
+
+  fn scene() -> Canvas {
+    let photo = DynamicImage::ImageRgba8(RgbaImage::from_fn(40, 30, |x, y| Rgba([x as u8 * 6, y as u8 * 8, 90, 255])));
+    let mut canvas = Canvas::new(640, 560);
+    canvas.set_background(Fill::vertical(&[Rgba([10, 20, 30, 255]), Rgba([90, 40, 10, 255])]));
+    canvas.add_layer(Layer::Circle {
+      center: (320, 280),
+      radius: 200,
+      fill:   Fill::image(photo.clone(), Default::default()),
+      border: None
+    });
+    canvas.add_layer(
+      Layer::Rect {
+        size:     (500, 300),
+        position: (-20, 130),
+        fill:     Rgba([255, 255, 255, 200]).into()
+      }
+      .with_opacity(0.5)
+      .with_blend(BlendMode::Overlay)
+    );
+    canvas.add_layer(Layer::Image {
+      scale:     1.0,
+      position:  (100, 250),
+      image:     photo,
+      transform: ImageTransform::avatar(120)
+    });
+    canvas
+  }
+
+  #[test]
+  fn test_parallel_render_matches_serial() {
+    let canvas = scene();
+    let mut serial = RgbaImage::new(canvas.width, canvas.height);
+    canvas.draw(&mut Surface::new(&mut serial));
+
+    // a pool of its own so the bands are split even on a single core runner
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+    assert_eq!(pool.install(|| canvas.render()).to_rgba8(), serial);
+  }
+
+  #[test]
+  fn test_scale_keeps_layout() {
+    let mut canvas = Canvas::new(20, 10);
+    canvas.add_layer(Layer::Line {
+      start: (0, 4),
+      end:   (19, 4),
+      width: 1,
+      color: Rgba([255, 255, 255, 255])
+    });
+    let img = canvas.with_scale(2.0).render().to_rgba8();
+
+    assert_eq!(img.dimensions(), (40, 20));
+    assert_eq!(img.get_pixel(10, 8).0[0], 255);
+    assert_eq!(img.get_pixel(10, 9).0[0], 255);
+    assert_eq!(img.get_pixel(10, 10).0[0], 0, "1px line should become 2px thick");
+  }
+
+  #[test]
+  fn test_render_async_and_cache() {
+    let canvas = scene();
+    let first = canvas.to_bytes(None).unwrap();
+    assert_eq!(canvas.to_bytes(None).unwrap(), first);
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let bytes = runtime.block_on(scene().to_bytes_async(None)).unwrap();
+    assert_eq!(bytes, first);
+  }
+
+  #[test]
+  fn test_parse_emotes() {
+    let unicode = |s: &str| EmoteSource::Unicode(s.to_string());
+    assert_eq!(
+      parse_emotes("hi <:wave:42> 👨‍👩‍👧 🇯🇵 👍🏽 1️⃣", None),
+      [
+        EmoteSource::Discord("42".to_string()),
+        unicode("👨‍👩‍👧"),
+        unicode("🇯🇵"),
+        unicode("👍🏽"),
+        unicode("1️⃣")
+      ]
+    );
+    assert_eq!(parse_all_emotes("漢字 © 2025 ↔ ★ :nope: :floppy_disk:"), [unicode("💾")]);
+    assert_eq!(parse_all_emotes("🕷 and 🕷️"), [unicode("🕷️"), unicode("🕷️")]);
+    assert_eq!(parse_all_emotes(":+1::fire: 😀 😀"), [unicode("👍"), unicode("🔥"), unicode("😀")]);
+    assert_eq!(parse_emotes(":+1::fire: 😀 😀", Some(1)), [unicode("👍")]);
+  }
+
+  #[test]
+  fn test_split_emotes() {
+    assert_eq!(
+      split_emotes("gg <:pog:7>!🔥"),
+      [
+        Segment::Text("gg "),
+        Segment::Emote(EmoteSource::Discord("7".to_string()), "<:pog:7>"),
+        Segment::Text("!"),
+        Segment::Emote(EmoteSource::Unicode("🔥".to_string()), "🔥")
+      ]
+    );
+  }
+
+  #[test]
+  fn test_truncate_text() {
+    let font = crate::Font::UbuntuRegular.to_fontarc();
+    assert_eq!(truncate_text("short", 20.0, &font, 200), "short");
+
+    let cut = truncate_text("a name far too long for the space", 20.0, &font, 100);
+    assert!(cut.ends_with('…') && assume_text_width(&cut, 20.0, &font) <= 100);
+  }
+
+  #[test]
+  fn test_twemoji_name() {
+    assert_eq!(twemoji_name("🕷️"), "1f577");
+    assert_eq!(twemoji_name("1️⃣"), "31-20e3");
+    assert_eq!(twemoji_name("🇯🇵"), "1f1ef-1f1f5");
+    assert_eq!(twemoji_name("👨‍👩‍👧"), "1f468-200d-1f469-200d-1f467");
+    assert_eq!(twemoji_name("🏳️‍🌈"), "1f3f3-fe0f-200d-1f308");
+  }
 }
@@ -0,0 +1,342 @@
+mod provider;
+
+pub(crate) use provider::emote_provider;
+#[cfg(test)]
+pub(crate) use provider::use_test_provider;
+pub use provider::{
+  CdnProvider,
+  DirectoryProvider,
+  EmojiSet,
+  EmoteProvider,
+  set_emote_provider
+};
+
+use {
+  crate::{
+    cache::LruCache,
+    canvas::EmoteSource
+  },
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
+  },
+  image::DynamicImage,
+  std::{
+    path::{
+      Path,
+      PathBuf
+    },
+    sync::{
+      LazyLock,
+      Mutex
+    },
+    time::{
+      Duration,
+      SystemTime
+    }
+  }
+};
+
+/// Caches the fetched emotes to avoid redownloading them
+pub(crate) static EMOTE_CACHE: LazyLock<Mutex<EmoteCache>> = LazyLock::new(|| Mutex::new(EmoteCache::new(EmoteCacheConfig::default())));
+
+/// Emote cache settings, see [configure_emote_cache]
+#[derive(Debug, Clone)]
+pub struct EmoteCacheConfig {
+  /// Memory budget for the decoded images in bytes<br>
+  /// Defaults to 64 MiB
+  pub max_bytes: usize,
+  /// How long a fetched emote is used before it's downloaded again, `None` keeps it until evicted<br>
+  /// Defaults to 24 hours
+  pub ttl:       Option<Duration>,
+  /// Directory keeping the downloaded files so they survive restarts<br>
+  /// Defaults to `None`, memory only
+  pub disk_dir:  Option<PathBuf>
+}
+
+impl Default for EmoteCacheConfig {
+  fn default() -> Self {
+    Self {
+      max_bytes: 64 * 1024 * 1024,
+      ttl:       Some(Duration::from_secs(24 * 60 * 60)),
+      disk_dir:  None
+    }
+  }
+}
+
+/// Counters of the emote cache since startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmoteCacheStats {
+  pub hits:      u64,
+  pub misses:    u64,
+  /// Emotes dropped from memory to stay within the budget or because they expired
+  pub evictions: u64,
+  /// Emotes currently held in memory
+  pub entries:   usize,
+  /// Memory used by the held emotes in bytes
+  pub bytes:     usize
+}
+
+pub(crate) struct EmoteCache {
+  images: LruCache<String, DynamicImage>,
+  config: EmoteCacheConfig,
+  hits:   u64,
+  misses: u64
+}
+
+/// Disk side of the emote cache, copied out of [EMOTE_CACHE] so files are read and written without holding it
+#[derive(Clone)]
+struct DiskCache {
+  dir: Option<PathBuf>,
+  ttl: Option<Duration>
+}
+
+impl EmoteCache {
+  fn new(config: EmoteCacheConfig) -> Self {
+    let mut images = LruCache::new(config.max_bytes);
+    images.set_ttl(config.ttl);
+    Self {
+      images,
+      config,
+      hits: 0,
+      misses: 0
+    }
+  }
+
+  /// Stores an already decoded image in memory
+  pub(crate) fn insert_image(
+    &mut self,
+    emote: &EmoteSource,
+    image: DynamicImage
+  ) {
+    let weight = weight(&image);
+    self.images.insert(emote.cache_key(), image, weight);
+  }
+
+  fn disk(&self) -> DiskCache {
+    DiskCache {
+      dir: self.config.disk_dir.clone(),
+      ttl: self.config.ttl
+    }
+  }
+
+  fn stats(&self) -> EmoteCacheStats {
+    EmoteCacheStats {
+      hits:      self.hits,
+      misses:    self.misses,
+      evictions: self.images.evictions(),
+      entries:   self.images.len(),
+      bytes:     self.images.weight()
+    }
+  }
+}
+
+impl DiskCache {
+  fn path(
+    &self,
+    key: &str
+  ) -> Option<PathBuf> {
+    let name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    Some(self.dir.as_ref()?.join(name))
+  }
+
+  fn is_fresh(
+    &self,
+    path: &Path
+  ) -> bool {
+    let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+      return false
+    };
+    self
+      .ttl
+      .is_none_or(|ttl| SystemTime::now().duration_since(modified).unwrap_or_default() < ttl)
+  }
+
+  fn contains(
+    &self,
+    key: &str
+  ) -> bool {
+    self.path(key).is_some_and(|path| self.is_fresh(&path))
+  }
+
+  fn read(
+    &self,
+    key: &str
+  ) -> Option<DynamicImage> {
+    let path = self.path(key)?;
+    if !self.is_fresh(&path) {
+      let _ = std::fs::remove_file(&path);
+      return None
+    }
+    image::load_from_memory(&std::fs::read(path).ok()?).ok()
+  }
+
+  fn write(
+    &self,
+    key: &str,
+    bytes: &[u8]
+  ) {
+    let Some(path) = self.path(key) else { return };
+    if let Err(e) = std::fs::write(&path, bytes) {
+      asahi_internal::warn!("failed to write emote {key} to the disk cache at {}: {e}", path.display());
+    }
+  }
+}
+
+/// Looks the emote up in memory, then in the disk cache, only holding the lock for the memory parts
+pub(crate) fn cached_emote(emote: &EmoteSource) -> Option<DynamicImage> { lookup(&EMOTE_CACHE, emote) }
+
+/// Whether the emote is available without fetching it, doesn't count towards the stats
+pub(crate) fn is_emote_cached(emote: &EmoteSource) -> bool { is_cached(&EMOTE_CACHE, emote) }
+
+/// Decodes the downloaded file and writes it to the disk cache, ready for [EmoteCache::insert_image]<br>
+/// Only reads the disk settings under the lock
+pub(crate) fn decode_emote(
+  emote: &EmoteSource,
+  bytes: &[u8]
+) -> Option<DynamicImage> {
+  decode(&EMOTE_CACHE, emote, bytes)
+}
+
+fn lookup(
+  cache: &Mutex<EmoteCache>,
+  emote: &EmoteSource
+) -> Option<DynamicImage> {
+  let key = emote.cache_key();
+  let disk = {
+    let mut cache = cache.lock().expect("failed to acquire lock");
+    if let Some(image) = cache.images.get(&key) {
+      cache.hits += 1;
+      return Some(image)
+    }
+    cache.disk()
+  };
+
+  let image = disk.read(&key);
+  let mut cache = cache.lock().expect("failed to acquire lock");
+  match &image {
+    Some(image) => {
+      cache.hits += 1;
+      cache.insert_image(emote, image.clone());
+    },
+    None => cache.misses += 1
+  }
+  image
+}
+
+fn is_cached(
+  cache: &Mutex<EmoteCache>,
+  emote: &EmoteSource
+) -> bool {
+  let key = emote.cache_key();
+  let disk = {
+    let cache = cache.lock().expect("failed to acquire lock");
+    if cache.images.contains(&key) {
+      return true
+    }
+    cache.disk()
+  };
+  disk.contains(&key)
+}
+
+fn decode(
+  cache: &Mutex<EmoteCache>,
+  emote: &EmoteSource,
+  bytes: &[u8]
+) -> Option<DynamicImage> {
+  let image = image::load_from_memory(bytes).ok()?;
+  let disk = cache.lock().expect("failed to acquire lock").disk();
+  disk.write(&emote.cache_key(), bytes);
+  Some(image)
+}
+
+fn weight(image: &DynamicImage) -> usize { image.as_bytes().len() }
+
+/// Replaces the emote cache settings, emotes already in memory are kept if they fit the new budget
+pub fn configure_emote_cache(config: EmoteCacheConfig) -> AsahiResult {
+  if let Some(dir) = &config.disk_dir {
+    std::fs::create_dir_all(dir).map_err(|e| AsahiError::Config(format!("failed to create emote cache at {}: {e}", dir.display()).into()))?;
+  }
+
+  let mut cache = EMOTE_CACHE.lock().expect("failed to acquire lock");
+  cache.images.set_capacity(config.max_bytes);
+  cache.images.set_ttl(config.ttl);
+  cache.config = config;
+  Ok(())
+}
+
+pub fn emote_cache_stats() -> EmoteCacheStats { EMOTE_CACHE.lock().expect("failed to acquire lock").stats() }
+
+/// Drops every emote held in memory, the disk cache is left alone
+pub fn clear_emote_cache() { EMOTE_CACHE.lock().expect("failed to acquire lock").images.clear(); }
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    image::{
+      ImageFormat,
+      RgbaImage
+    },
+    std::io::Cursor
+  };
+
+  fn png() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(RgbaImage::new(8, 8))
+      .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+      .unwrap();
+    bytes
+  }
+
+  #[test]
+  fn test_disk_cache_survives_restart() {
+    let dir = std::env::temp_dir().join(format!("asahi_emotes_{}", std::process::id()));
+    let config = EmoteCacheConfig {
+      disk_dir: Some(dir.clone()),
+      ..Default::default()
+    };
+    std::fs::create_dir_all(&dir).unwrap();
+    let emote = EmoteSource::Discord("1234".to_string());
+
+    let cache = Mutex::new(EmoteCache::new(config.clone()));
+    assert!(lookup(&cache, &emote).is_none());
+    let image = decode(&cache, &emote, &png()).unwrap();
+    cache.lock().unwrap().insert_image(&emote, image);
+
+    let restarted = Mutex::new(EmoteCache::new(config));
+    assert!(is_cached(&restarted, &emote));
+    assert!(lookup(&restarted, &emote).is_some());
+    assert_eq!((restarted.lock().unwrap().stats().hits, cache.lock().unwrap().stats().misses), (1, 1));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_disk_write_failure() {
+    let file = std::env::temp_dir().join(format!("asahi_emotes_file_{}", std::process::id()));
+    std::fs::write(&file, []).unwrap();
+    let cache = Mutex::new(EmoteCache::new(EmoteCacheConfig {
+      disk_dir: Some(file.clone()),
+      ..Default::default()
+    }));
+
+    // the disk cache sits under a plain file, so writing fails but the emote still decodes
+    assert!(decode(&cache, &EmoteSource::Discord("1".to_string()), &png()).is_some());
+    std::fs::remove_file(file).unwrap();
+  }
+
+  #[test]
+  fn test_memory_budget() {
+    let mut cache = EmoteCache::new(EmoteCacheConfig {
+      max_bytes: 8 * 8 * 4 * 2,
+      ..Default::default()
+    });
+    for id in 0..3 {
+      cache.insert_image(&EmoteSource::Discord(id.to_string()), image::load_from_memory(&png()).unwrap());
+    }
+
+    let stats = cache.stats();
+    assert_eq!((stats.entries, stats.evictions), (2, 1));
+  }
+}
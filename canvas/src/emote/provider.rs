@@ -0,0 +1,292 @@
+use {
+  crate::canvas::{
+    EmoteSource,
+    twemoji_name
+  },
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
+  },
+  async_trait::async_trait,
+  reqwest::Client,
+  std::{
+    path::PathBuf,
+    sync::{
+      Arc,
+      LazyLock,
+      RwLock
+    },
+    time::Duration
+  }
+};
+
+/// HTTP client shared by every [CdnProvider]
+static HTTP_CLIENT: LazyLock<Client> = LazyLock::new(|| {
+  Client::builder()
+    .connect_timeout(Duration::from_secs(5))
+    .timeout(Duration::from_secs(15))
+    .build()
+    .expect("failed to build HTTP client")
+});
+
+static EMOTE_PROVIDER: LazyLock<RwLock<Arc<dyn EmoteProvider>>> = LazyLock::new(|| RwLock::new(Arc::new(CdnProvider::default())));
+
+/// Where emote images come from, the default is [CdnProvider]<br>
+/// Swap it with [set_emote_provider], e.g. for a [DirectoryProvider] or a stub in tests
+///
+/// ```
+/// use {
+///   asahi_canvas::{
+///     EmoteProvider,
+///     EmoteSource,
+///     async_trait,
+///     parse_all_emotes,
+///     set_emote_provider
+///   },
+///   asahi_internal::{
+///     AsahiError,
+///     AsahiResult
+///   }
+/// };
+///
+/// /// Serves custom emotes from the bot's assets, emojis aren't bundled
+/// struct Bundled;
+///
+/// #[async_trait]
+/// impl EmoteProvider for Bundled {
+///   async fn fetch(
+///     &self,
+///     emote: &EmoteSource
+///   ) -> AsahiResult<Vec<u8>> {
+///     match emote {
+///       EmoteSource::Discord(id) => std::fs::read(format!("assets/emotes/{id}.png")).map_err(|e| AsahiError::External(e.to_string().into())),
+///       EmoteSource::Unicode(emoji) => Err(AsahiError::External(format!("{emoji} isn't bundled").into()))
+///     }
+///   }
+/// }
+///
+/// set_emote_provider(Bundled);
+/// let emotes: Vec<EmoteSource> = parse_all_emotes("gg <:pog:501>");
+/// assert_eq!(emotes, [EmoteSource::Discord("501".to_string())]);
+/// ```
+#[async_trait]
+pub trait EmoteProvider: Send + Sync {
+  /// Original file bytes of the emote, PNG, WebP, GIF or any other format the `image` crate decodes
+  async fn fetch(
+    &self,
+    emote: &EmoteSource
+  ) -> AsahiResult<Vec<u8>>;
+}
+
+/// Emoji images served as `{base_url}/{codepoints}.png`, codepoints being in lowercase hex joined by `-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmojiSet {
+  /// 72x72 Twemoji PNGs of given version from cdnjs
+  Twemoji(String),
+  /// Any set following the Twemoji naming, given by its base URL
+  Custom(String)
+}
+
+impl Default for EmojiSet {
+  fn default() -> Self { EmojiSet::Twemoji("16.0.1".to_string()) }
+}
+
+impl EmojiSet {
+  fn base_url(&self) -> String {
+    match self {
+      EmojiSet::Twemoji(version) => format!("https://cdnjs.cloudflare.com/ajax/libs/twemoji/{version}/72x72"),
+      EmojiSet::Custom(url) => url.trim_end_matches('/').to_string()
+    }
+  }
+}
+
+/// Downloads Discord emotes from Discord's CDN and emojis from the [EmojiSet]
+#[derive(Debug, Clone)]
+pub struct CdnProvider {
+  /// Defaults to `https://cdn.discordapp.com`
+  pub discord_url: String,
+  pub emoji_set:   EmojiSet
+}
+
+impl Default for CdnProvider {
+  fn default() -> Self {
+    Self {
+      discord_url: "https://cdn.discordapp.com".to_string(),
+      emoji_set:   EmojiSet::default()
+    }
+  }
+}
+
+impl CdnProvider {
+  fn url(
+    &self,
+    emote: &EmoteSource
+  ) -> AsahiResult<String> {
+    let stem = file_stem(emote)?;
+    Ok(match emote {
+      EmoteSource::Discord(_) => format!("{}/emojis/{stem}.webp?size=96", self.discord_url.trim_end_matches('/')),
+      EmoteSource::Unicode(_) => format!("{}/{stem}.png", self.emoji_set.base_url())
+    })
+  }
+}
+
+#[async_trait]
+impl EmoteProvider for CdnProvider {
+  async fn fetch(
+    &self,
+    emote: &EmoteSource
+  ) -> AsahiResult<Vec<u8>> {
+    let resp = HTTP_CLIENT.get(self.url(emote)?).send().await?.error_for_status()?;
+    Ok(resp.bytes().await?.to_vec())
+  }
+}
+
+/// Loads emotes from a local directory, Discord emotes as `{id}.png`, `{id}.webp` or `{id}.gif`
+/// and emojis named like Twemoji (`1f577.png`, `1f1ef-1f1f5.png`), so an extracted Twemoji asset pack works as is
+#[derive(Debug, Clone)]
+pub struct DirectoryProvider {
+  pub dir: PathBuf
+}
+
+impl DirectoryProvider {
+  pub fn new(dir: impl Into<PathBuf>) -> Self { Self { dir: dir.into() } }
+}
+
+#[async_trait]
+impl EmoteProvider for DirectoryProvider {
+  async fn fetch(
+    &self,
+    emote: &EmoteSource
+  ) -> AsahiResult<Vec<u8>> {
+    let stem = file_stem(emote)?;
+    let extensions: &[&str] = match emote {
+      EmoteSource::Discord(_) => &["png", "webp", "gif"],
+      EmoteSource::Unicode(_) => &["png"]
+    };
+    let paths: Vec<_> = extensions.iter().map(|ext| self.dir.join(format!("{stem}.{ext}"))).collect();
+
+    let found = tokio::task::spawn_blocking(move || paths.iter().find_map(|path| std::fs::read(path).ok()))
+      .await
+      .map_err(|e| AsahiError::Worker(format!("emote read task failed: {e}").into()))?;
+    found.ok_or_else(|| AsahiError::External(format!("emote {stem} not found in {}", self.dir.display()).into()))
+  }
+}
+
+/// File name of the emote without extension, Discord IDs must be all digits so they can't walk out of a path or URL
+pub(crate) fn file_stem(emote: &EmoteSource) -> AsahiResult<String> {
+  match emote {
+    EmoteSource::Discord(id) if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => Ok(id.clone()),
+    EmoteSource::Discord(id) => Err(AsahiError::Parse(format!("invalid Discord emote ID `{id}`").into())),
+    EmoteSource::Unicode(emoji) => Ok(twemoji_name(emoji))
+  }
+}
+
+/// Replaces the provider used to fetch emotes that aren't cached yet
+pub fn set_emote_provider(provider: impl EmoteProvider + 'static) { *EMOTE_PROVIDER.write().expect("failed to acquire lock") = Arc::new(provider); }
+
+pub(crate) fn emote_provider() -> Arc<dyn EmoteProvider> { EMOTE_PROVIDER.read().expect("failed to acquire lock").clone() }
+
+/// Serialises the tests that swap the provider so they can't see each other's
+#[cfg(test)]
+static PROVIDER_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Provider swapped in by [use_test_provider], the previous one is put back on drop
+#[cfg(test)]
+pub(crate) struct ProviderGuard {
+  previous: Option<Arc<dyn EmoteProvider>>,
+  _lock:    std::sync::MutexGuard<'static, ()>
+}
+
+#[cfg(test)]
+impl Drop for ProviderGuard {
+  fn drop(&mut self) {
+    if let Some(previous) = self.previous.take() {
+      *EMOTE_PROVIDER.write().unwrap_or_else(|e| e.into_inner()) = previous;
+    }
+  }
+}
+
+/// Sets the provider until the returned guard is dropped
+#[cfg(test)]
+pub(crate) fn use_test_provider(provider: impl EmoteProvider + 'static) -> ProviderGuard {
+  let lock = PROVIDER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let previous = std::mem::replace(&mut *EMOTE_PROVIDER.write().unwrap_or_else(|e| e.into_inner()), Arc::new(provider));
+  ProviderGuard {
+    previous: Some(previous),
+    _lock:    lock
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    std::{
+      io::{
+        Read,
+        Write
+      },
+      net::TcpListener
+    }
+  };
+
+  /// Serves one request with the body and returns the requested path
+  fn serve_once(body: &'static [u8]) -> (String, std::thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut buf = [0; 1024];
+      let n = stream.read(&mut buf).unwrap();
+      let request = String::from_utf8_lossy(&buf[..n]).to_string();
+      write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+      stream.write_all(body).unwrap();
+      request.split_whitespace().nth(1).unwrap_or_default().to_string()
+    });
+
+    (url, handle)
+  }
+
+  fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+      .block_on(future)
+  }
+
+  #[test]
+  fn test_cdn_uses_configured_urls() {
+    let (url, server) = serve_once(b"emoji");
+    let provider = CdnProvider {
+      emoji_set: EmojiSet::Custom(format!("{url}/assets/")),
+      ..Default::default()
+    };
+
+    let bytes = block_on(provider.fetch(&EmoteSource::Unicode("🕷️".to_string()))).unwrap();
+    assert_eq!(bytes, b"emoji");
+    assert_eq!(server.join().unwrap(), "/assets/1f577.png");
+  }
+
+  #[test]
+  fn test_directory_provider() {
+    let dir = std::env::temp_dir().join(format!("asahi_provider_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("42.webp"), b"discord").unwrap();
+    let provider = DirectoryProvider::new(&dir);
+
+    assert_eq!(block_on(provider.fetch(&EmoteSource::Discord("42".to_string()))).unwrap(), b"discord");
+    assert!(block_on(provider.fetch(&EmoteSource::Unicode("💾".to_string()))).is_err());
+
+    std::fs::write(dir.with_extension("png"), b"outside").unwrap();
+    let escape = format!("../{}", dir.file_name().unwrap().to_string_lossy());
+    assert!(matches!(
+      block_on(provider.fetch(&EmoteSource::Discord(escape))),
+      Err(AsahiError::Parse(_))
+    ));
+    std::fs::remove_file(dir.with_extension("png")).unwrap();
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
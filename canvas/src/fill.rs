@@ -0,0 +1,336 @@
+use {
+  crate::{
+    cache::hash_image,
+    raster::Prepared,
+    scene::{
+      color,
+      image_data
+    }
+  },
+  image::{
+    DynamicImage,
+    GenericImageView,
+    Rgba,
+    RgbaImage,
+    imageops::{
+      FilterType,
+      blur
+    }
+  },
+  serde::{
+    Deserialize,
+    Serialize
+  },
+  std::{
+    borrow::Cow,
+    hash::{
+      Hash,
+      Hasher
+    }
+  }
+};
+
+/// Paint used for the canvas background and shape layers<br>
+/// Gradient coordinates are relative to the filled area, `(0.0, 0.0)` being its top-left corner and `(1.0, 1.0)` the bottom-right
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fill {
+  Solid(#[serde(with = "color")] Rgba<u8>),
+  LinearGradient {
+    start: (f32, f32),
+    end:   (f32, f32),
+    stops: Vec<ColorStop>
+  },
+  RadialGradient {
+    center: (f32, f32),
+    /// Relative to the longer side of the filled area
+    radius: f32,
+    stops:  Vec<ColorStop>
+  },
+  Image {
+    #[serde(with = "image_data")]
+    image: DynamicImage,
+    #[serde(default)]
+    fit:   ImageFit,
+    /// Gaussian blur sigma, `0.0` disables it
+    #[serde(default)]
+    blur:  f32
+  }
+}
+
+/// Color at a given offset (`0.0` to `1.0`) along a gradient
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ColorStop {
+  pub offset: f32,
+  #[serde(with = "color")]
+  pub color:  Rgba<u8>
+}
+
+/// How an image is sized into the area it fills
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFit {
+  /// Scales the image to cover the whole area, cropping the overflow
+  #[default]
+  Cover,
+  /// Scales the image to fit inside the area, leaving the rest transparent
+  Contain,
+  /// Stretches the image to the exact size of the area
+  Stretch
+}
+
+/// Floats are hashed by their bits, images by their pixels
+impl Hash for Fill {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    std::mem::discriminant(self).hash(state);
+    match self {
+      Fill::Solid(color) => color.hash(state),
+      Fill::LinearGradient { start, end, stops } => (bits(*start), bits(*end), stops).hash(state),
+      Fill::RadialGradient { center, radius, stops } => (bits(*center), radius.to_bits(), stops).hash(state),
+      Fill::Image { image, fit, blur } => {
+        hash_image(image, state);
+        (fit, blur.to_bits()).hash(state);
+      }
+    }
+  }
+}
+
+impl Hash for ColorStop {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    (self.offset.to_bits(), self.color).hash(state);
+  }
+}
+
+pub(crate) fn bits(p: (f32, f32)) -> (u32, u32) { (p.0.to_bits(), p.1.to_bits()) }
+
+impl ColorStop {
+  pub fn new(
+    offset: f32,
+    color: Rgba<u8>
+  ) -> Self {
+    Self { offset, color }
+  }
+}
+
+impl From<Rgba<u8>> for Fill {
+  fn from(color: Rgba<u8>) -> Self { Fill::Solid(color) }
+}
+
+impl Fill {
+  /// Horizontal gradient going from left to right through evenly spaced colors
+  pub fn horizontal(colors: &[Rgba<u8>]) -> Self {
+    Fill::LinearGradient {
+      start: (0.0, 0.0),
+      end:   (1.0, 0.0),
+      stops: even_stops(colors)
+    }
+  }
+
+  /// Vertical gradient going from top to bottom through evenly spaced colors
+  pub fn vertical(colors: &[Rgba<u8>]) -> Self {
+    Fill::LinearGradient {
+      start: (0.0, 0.0),
+      end:   (0.0, 1.0),
+      stops: even_stops(colors)
+    }
+  }
+
+  /// Image fill without blur
+  pub fn image(
+    image: DynamicImage,
+    fit: ImageFit
+  ) -> Self {
+    Fill::Image { image, fit, blur: 0.0 }
+  }
+
+  /// Resized image of an image fill covering an area of given size, computed once before a parallel render
+  pub(crate) fn prepare(
+    &self,
+    size: (f32, f32)
+  ) -> Option<RgbaImage> {
+    let Fill::Image { image, fit, blur } = self else { return None };
+    Some(fit_image(image, area(size), *fit, *blur, FilterType::Lanczos3))
+  }
+
+  /// Prepares the fill for painting the area at `origin` with given size,
+  /// reusing the image from `prepared` if [Fill::prepare] was done for it
+  pub(crate) fn shader<'a>(
+    &'a self,
+    origin: (f32, f32),
+    size: (f32, f32),
+    prepared: Option<&'a Prepared>
+  ) -> Shader<'a> {
+    let to_px = |p: (f32, f32)| (origin.0 + p.0 * size.0, origin.1 + p.1 * size.1);
+
+    match self {
+      Fill::Solid(color) => Shader::Solid(*color),
+      Fill::LinearGradient { start, end, stops } => {
+        let start = to_px(*start);
+        let end = to_px(*end);
+        let dir = (end.0 - start.0, end.1 - start.1);
+        let len = dir.0 * dir.0 + dir.1 * dir.1;
+
+        Shader::Linear {
+          start,
+          dir: if len == 0.0 { (0.0, 0.0) } else { (dir.0 / len, dir.1 / len) },
+          stops
+        }
+      },
+      Fill::RadialGradient { center, radius, stops } => Shader::Radial {
+        center: to_px(*center),
+        radius: (radius * size.0.max(size.1)).max(f32::EPSILON),
+        stops
+      },
+      Fill::Image { image, fit, blur } => {
+        let area = area(size);
+        let image = match prepared.and_then(|p| p.get(&(self as *const Fill as usize))) {
+          Some(fitted) if fitted.dimensions() == (area.0.max(1), area.1.max(1)) => Cow::Borrowed(fitted),
+          _ => Cow::Owned(fit_image(image, area, *fit, *blur, FilterType::Lanczos3))
+        };
+
+        Shader::Image {
+          image,
+          origin: (origin.0.floor() as i64, origin.1.floor() as i64)
+        }
+      }
+    }
+  }
+}
+
+/// Fill resolved for a specific area, ready to be sampled per pixel
+pub(crate) enum Shader<'a> {
+  Solid(Rgba<u8>),
+  Linear {
+    start: (f32, f32),
+    dir:   (f32, f32),
+    stops: &'a [ColorStop]
+  },
+  Radial {
+    center: (f32, f32),
+    radius: f32,
+    stops:  &'a [ColorStop]
+  },
+  Image {
+    image:  Cow<'a, RgbaImage>,
+    origin: (i64, i64)
+  }
+}
+
+impl Shader<'_> {
+  /// Color of the pixel at given canvas coordinates
+  pub(crate) fn at(
+    &self,
+    x: i32,
+    y: i32
+  ) -> Rgba<u8> {
+    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+    match self {
+      Shader::Solid(color) => *color,
+      Shader::Linear { start, dir, stops } => sample_stops(stops, (px - start.0) * dir.0 + (py - start.1) * dir.1),
+      Shader::Radial { center, radius, stops } => sample_stops(stops, (px - center.0).hypot(py - center.1) / radius),
+      Shader::Image { image, origin } => {
+        let (ix, iy) = (x as i64 - origin.0, y as i64 - origin.1);
+        if ix < 0 || iy < 0 || ix >= image.width() as i64 || iy >= image.height() as i64 {
+          return Rgba([0, 0, 0, 0])
+        }
+        *image.get_pixel(ix as u32, iy as u32)
+      }
+    }
+  }
+}
+
+fn area(size: (f32, f32)) -> (u32, u32) { (size.0.ceil() as u32, size.1.ceil() as u32) }
+
+fn even_stops(colors: &[Rgba<u8>]) -> Vec<ColorStop> {
+  let last = colors.len().saturating_sub(1).max(1) as f32;
+  colors.iter().enumerate().map(|(i, c)| ColorStop::new(i as f32 / last, *c)).collect()
+}
+
+fn sample_stops(
+  stops: &[ColorStop],
+  t: f32
+) -> Rgba<u8> {
+  let Some(first) = stops.first() else { return Rgba([0, 0, 0, 0]) };
+
+  if t <= first.offset {
+    return first.color
+  }
+
+  for pair in stops.windows(2) {
+    let (a, b) = (pair[0], pair[1]);
+    if t <= b.offset {
+      let span = b.offset - a.offset;
+      let k = if span <= 0.0 { 1.0 } else { (t - a.offset) / span };
+      let mut out = [0u8; 4];
+      for (i, channel) in out.iter_mut().enumerate() {
+        *channel = (a.color.0[i] as f32 + (b.color.0[i] as f32 - a.color.0[i] as f32) * k).round() as u8;
+      }
+      return Rgba(out)
+    }
+  }
+
+  stops[stops.len() - 1].color
+}
+
+/// Resizes the image into the area according to the fit mode and applies the blur
+pub(crate) fn fit_image(
+  image: &DynamicImage,
+  area: (u32, u32),
+  fit: ImageFit,
+  sigma: f32,
+  filter: FilterType
+) -> RgbaImage {
+  let (aw, ah) = (area.0.max(1), area.1.max(1));
+  let (w, h) = image.dimensions();
+  let mut out = RgbaImage::new(aw, ah);
+  if w == 0 || h == 0 {
+    return out
+  }
+
+  let fitted = match fit {
+    ImageFit::Stretch => image.resize_exact(aw, ah, filter),
+    ImageFit::Cover => image.resize_to_fill(aw, ah, filter),
+    ImageFit::Contain => image.resize(aw, ah, filter)
+  };
+  let fitted = if sigma > 0.0 { blur(&fitted, sigma) } else { fitted.to_rgba8() };
+
+  let x = (aw - fitted.width().min(aw)) / 2;
+  let y = (ah - fitted.height().min(ah)) / 2;
+  image::imageops::replace(&mut out, &fitted, x.into(), y.into());
+  out
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_linear_gradient_endpoints() {
+    let fill = Fill::horizontal(&[Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255])]);
+    let shader = fill.shader((0.0, 0.0), (100.0, 10.0), None);
+
+    assert_eq!(shader.at(0, 5).0[0], 1);
+    assert!((126..=129).contains(&shader.at(49, 5).0[0]));
+    assert_eq!(shader.at(99, 5).0[0], 254);
+  }
+
+  #[test]
+  fn test_image_fit_modes() {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 100, Rgba([255, 0, 0, 255])));
+
+    let cover = fit_image(&image, (50, 50), ImageFit::Cover, 0.0, FilterType::Lanczos3);
+    assert_eq!(cover.dimensions(), (50, 50));
+    assert_eq!(cover.get_pixel(0, 0).0[3], 255);
+
+    let contain = fit_image(&image, (50, 50), ImageFit::Contain, 0.0, FilterType::Lanczos3);
+    assert_eq!(contain.get_pixel(25, 0).0[3], 0, "letterbox should stay transparent");
+    assert_eq!(contain.get_pixel(25, 25).0[3], 255);
+  }
+}
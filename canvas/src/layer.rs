@@ -1,72 +1,482 @@
 use {
+  crate::{
+    cache::hash_image,
+    canvas::assume_text_width,
+    fill::{
+      Fill,
+      Shader
+    },
+    raster::{
+      BlendMode,
+      Prepared,
+      Region,
+      Surface,
+      ellipse_sdf,
+      points_bounds,
+      polygon_sdf,
+      rounded_rect_sdf,
+      segment_distance,
+      stroke
+    },
+    scene::{
+      self,
+      color,
+      image_data
+    },
+    transform::ImageTransform
+  },
   ab_glyph::{
+    Font as _,
     FontArc,
-    PxScale
+    PxScale,
+    ScaleFont
   },
   image::{
     DynamicImage,
     GenericImageView,
     Rgba,
-    RgbaImage,
-    imageops::{
-      Lanczos3,
-      overlay
-    }
+    RgbaImage
+  },
+  serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::Error as _
   },
-  imageproc::{
-    drawing::{
-      draw_filled_rect_mut,
-      draw_line_segment_mut,
-      draw_text_mut
+  std::{
+    borrow::Cow,
+    collections::HashMap,
+    hash::{
+      Hash,
+      Hasher
     },
-    rect::Rect
+    sync::{
+      LazyLock,
+      Mutex
+    }
   }
 };
 
+/// Drawable element of a [Canvas](crate::Canvas), positions are in canvas pixels and may be negative or
+/// past the edges, anything outside is clipped
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Layer {
   Rect {
     size:     (u32, u32),
-    position: (u32, u32),
-    color:    Rgba<u8>
+    position: (i32, i32),
+    fill:     Fill
   },
+  /// Rectangle with its corners rounded by `radius`
+  RoundedRect {
+    size:     (u32, u32),
+    position: (i32, i32),
+    radius:   u32,
+    fill:     Fill,
+    /// Draws only the outline with given width instead of filling the shape
+    border:   Option<u32>
+  },
+  Circle {
+    center: (i32, i32),
+    radius: u32,
+    fill:   Fill,
+    /// Draws only the outline with given width instead of filling the shape
+    border: Option<u32>
+  },
+  Ellipse {
+    center: (i32, i32),
+    radii:  (u32, u32),
+    fill:   Fill,
+    /// Draws only the outline with given width instead of filling the shape
+    border: Option<u32>
+  },
+  /// Closed shape through the given points, filled with the even-odd rule
+  Polygon {
+    points: Vec<(i32, i32)>,
+    fill:   Fill,
+    /// Draws only the outline with given width instead of filling the shape
+    border: Option<u32>
+  },
+  /// Anti-aliased line with round caps
   Line {
-    start: (u32, u32),
-    end:   (u32, u32),
+    start: (i32, i32),
+    end:   (i32, i32),
     width: u32,
+    #[serde(with = "color")]
     color: Rgba<u8>
   },
   Text {
     size:     f32,
-    position: (u32, u32),
+    position: (i32, i32),
+    #[serde(with = "color")]
     color:    Rgba<u8>,
     content:  String,
     font:     Font
   },
   Image {
-    scale:    f32,
-    position: (u32, u32),
-    image:    DynamicImage
+    #[serde(default = "one")]
+    scale:     f32,
+    position:  (i32, i32),
+    #[serde(with = "image_data")]
+    image:     DynamicImage,
+    /// Masking, fitting, rotation and filters, see [ImageTransform]
+    #[serde(default)]
+    transform: ImageTransform
+  },
+  /// Draws the inner layers as one unit, see [Layer::group] and its `with_*` builders
+  Group {
+    layers:  Vec<Layer>,
+    /// Translation applied to every inner layer
+    #[serde(default)]
+    offset:  (i32, i32),
+    /// Aligns the content's bounding box so this point of it sits at `offset`,
+    /// plain translation if `None`
+    #[serde(default)]
+    anchor:  Option<Anchor>,
+    /// Only the content inside this region is drawn, it moves along with the content
+    #[serde(default)]
+    clip:    Option<Region>,
+    /// Multiplier for the alpha of everything in the group, from `0.0` to `1.0`
+    #[serde(default = "one")]
+    opacity: f32,
+    #[serde(default)]
+    blend:   BlendMode
+  }
+}
+
+/// Point of a layer's bounding box used for alignment
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+  #[default]
+  TopLeft,
+  Top,
+  TopRight,
+  Left,
+  Center,
+  Right,
+  BottomLeft,
+  Bottom,
+  BottomRight
+}
+
+impl Anchor {
+  /// Fraction of the width and height where the anchor lies
+  fn factors(self) -> (f32, f32) {
+    match self {
+      Anchor::TopLeft => (0.0, 0.0),
+      Anchor::Top => (0.5, 0.0),
+      Anchor::TopRight => (1.0, 0.0),
+      Anchor::Left => (0.0, 0.5),
+      Anchor::Center => (0.5, 0.5),
+      Anchor::Right => (1.0, 0.5),
+      Anchor::BottomLeft => (0.0, 1.0),
+      Anchor::Bottom => (0.5, 1.0),
+      Anchor::BottomRight => (1.0, 1.0)
+    }
+  }
+
+  /// Position of the anchor within the region
+  pub fn point(
+    self,
+    region: &Region
+  ) -> (i32, i32) {
+    let (fx, fy) = self.factors();
+    (
+      region.x + (region.width as f32 * fx).round() as i32,
+      region.y + (region.height as f32 * fy).round() as i32
+    )
   }
 }
 
 impl Layer {
+  /// Wraps the layers into a group without any offset, clipping or blending
+  pub fn group(layers: Vec<Layer>) -> Self {
+    Layer::Group {
+      layers,
+      offset: (0, 0),
+      anchor: None,
+      clip: None,
+      opacity: 1.0,
+      blend: BlendMode::Normal
+    }
+  }
+
+  /// Moves this layer by given offset, wrapping it into a group if needed
+  pub fn with_offset(
+    mut self,
+    value: (i32, i32)
+  ) -> Self {
+    if let Layer::Group { offset, .. } = self.ensure_group() {
+      *offset = value;
+    }
+    self
+  }
+
+  /// Places this layer so its anchor point lands at given position, wrapping it into a group if needed
+  pub fn anchored(
+    mut self,
+    value: Anchor,
+    at: (i32, i32)
+  ) -> Self {
+    if let Layer::Group { anchor, offset, .. } = self.ensure_group() {
+      *anchor = Some(value);
+      *offset = at;
+    }
+    self
+  }
+
+  /// Clips this layer to given region, wrapping it into a group if needed
+  pub fn with_clip(
+    mut self,
+    region: Region
+  ) -> Self {
+    if let Layer::Group { clip, .. } = self.ensure_group() {
+      *clip = Some(region);
+    }
+    self
+  }
+
+  /// Sets the opacity of this layer, wrapping it into a group if needed
+  pub fn with_opacity(
+    mut self,
+    value: f32
+  ) -> Self {
+    if let Layer::Group { opacity, .. } = self.ensure_group() {
+      *opacity = value.clamp(0.0, 1.0);
+    }
+    self
+  }
+
+  /// Sets the blend mode of this layer, wrapping it into a group if needed
+  pub fn with_blend(
+    mut self,
+    mode: BlendMode
+  ) -> Self {
+    if let Layer::Group { blend, .. } = self.ensure_group() {
+      *blend = mode;
+    }
+    self
+  }
+
+  fn ensure_group(&mut self) -> &mut Self {
+    if !matches!(self, Layer::Group { .. }) {
+      let layer = std::mem::replace(self, Layer::group(Vec::new()));
+      *self = Layer::group(vec![layer]);
+    }
+    self
+  }
+
+  /// Area covered by this layer in canvas pixels, `None` if it draws nothing
+  pub fn bounds(&self) -> Option<Region> {
+    match self {
+      Layer::Rect { size, position, .. } | Layer::RoundedRect { size, position, .. } => Some(Region::new(position.0, position.1, size.0, size.1)),
+      Layer::Circle { center, radius, .. } => Some(Region::new(center.0 - *radius as i32, center.1 - *radius as i32, radius * 2, radius * 2)),
+      Layer::Ellipse { center, radii, .. } => Some(Region::new(
+        center.0 - radii.0 as i32,
+        center.1 - radii.1 as i32,
+        radii.0 * 2,
+        radii.1 * 2
+      )),
+      Layer::Polygon { points, .. } => {
+        if points.is_empty() {
+          return None
+        }
+        Some(Region::from_bounds(points_bounds(&to_f32(points))))
+      },
+      Layer::Line { start, end, width, .. } => {
+        let (a, b, half) = line_geometry(*start, *end, *width, View::default());
+        let (x0, y0, x1, y1) = points_bounds(&[a, b]);
+        Some(Region::from_bounds((x0 - half, y0 - half, x1 + half, y1 + half)))
+      },
+      Layer::Text {
+        size,
+        position,
+        content,
+        font,
+        ..
+      } => {
+        let font = font.to_fontarc();
+        let height = font.as_scaled(PxScale::from(*size)).height().ceil() as u32;
+        Some(Region::new(position.0, position.1, assume_text_width(content, *size, &font), height))
+      },
+      Layer::Image {
+        scale,
+        position,
+        image,
+        transform
+      } => {
+        let (w, h) = transform.size.unwrap_or_else(|| {
+          let (w, h) = image.dimensions();
+          ((w as f32 * scale) as u32, (h as f32 * scale) as u32)
+        });
+        let (sin, cos) = transform.rotation.to_radians().sin_cos();
+        let rw = (w as f32 * cos.abs() + h as f32 * sin.abs()).ceil() as u32;
+        let rh = (w as f32 * sin.abs() + h as f32 * cos.abs()).ceil() as u32;
+        Some(Region::new(
+          position.0 - (rw as i32 - w as i32) / 2,
+          position.1 - (rh as i32 - h as i32) / 2,
+          rw,
+          rh
+        ))
+      },
+      Layer::Group { layers, clip, .. } => {
+        let content = layers.iter().filter_map(Layer::bounds).reduce(|a, b| a.union(&b))?;
+        let content = match clip {
+          Some(clip) => content.intersect(clip),
+          None => content
+        };
+        Some(content.translate(self.group_shift()?))
+      }
+    }
+  }
+
+  /// Translation a group applies to its content
+  pub(crate) fn group_shift(&self) -> Option<(i32, i32)> {
+    let Layer::Group { layers, offset, anchor, .. } = self else {
+      return Some((0, 0))
+    };
+
+    match anchor {
+      None => Some(*offset),
+      Some(anchor) => {
+        let content = layers.iter().filter_map(Layer::bounds).reduce(|a, b| a.union(&b))?;
+        let (ax, ay) = anchor.point(&content);
+        Some((offset.0 - ax, offset.1 - ay))
+      }
+    }
+  }
+
+  /// Shape fill along with the size of the area it paints at given scale
+  fn fill_area(
+    &self,
+    scale: f32
+  ) -> Option<(&Fill, (f32, f32))> {
+    let (fill, (w, h)) = match self {
+      Layer::Rect { size, fill, .. } | Layer::RoundedRect { size, fill, .. } => (fill, (size.0 as f32, size.1 as f32)),
+      Layer::Circle { radius, fill, .. } => (fill, (*radius as f32 * 2.0, *radius as f32 * 2.0)),
+      Layer::Ellipse { radii, fill, .. } => (fill, (radii.0 as f32 * 2.0, radii.1 as f32 * 2.0)),
+      Layer::Polygon { points, fill, .. } if points.len() >= 3 => {
+        let (x0, y0, x1, y1) = points_bounds(&to_f32(points));
+        (fill, (x1 - x0, y1 - y0))
+      },
+      _ => return None
+    };
+    Some((fill, (w * scale, h * scale)))
+  }
+
+  /// Resizes the images this layer and its children draw at given scale ahead of time, see [Prepared]
+  pub(crate) fn prepare(
+    &self,
+    scale: f32
+  ) -> Vec<(usize, RgbaImage)> {
+    match self {
+      Layer::Image {
+        image,
+        transform,
+        scale: own,
+        ..
+      } => {
+        vec![(self as *const Layer as usize, transform.scaled(scale).apply(image, own * scale))]
+      },
+      Layer::Group { layers, .. } => layers.iter().flat_map(|layer| layer.prepare(scale)).collect(),
+      layer => layer
+        .fill_area(scale)
+        .and_then(|(fill, size)| Some((fill as *const Fill as usize, fill.prepare(size)?)))
+        .into_iter()
+        .collect()
+    }
+  }
+
   pub fn render(
     &self,
     img: &mut RgbaImage
+  ) {
+    self.draw(&mut Surface::new(img), View::default());
+  }
+
+  /// Draws the layer mapped into device pixels by the view
+  pub(crate) fn draw(
+    &self,
+    surface: &mut Surface,
+    view: View
   ) {
     match self {
-      Layer::Rect { size, position, color } => {
-        let rect = Rect::at(position.0 as i32, position.1 as i32).of_size(size.0, size.1);
-        draw_filled_rect_mut(img, rect, *color);
+      Layer::Rect { size, position, fill } => {
+        let (x0, y0) = view.point(*position);
+        let (x1, y1) = view.point((position.0 + size.0 as i32, position.1 + size.1 as i32));
+        let bounds = (x0, y0, x1, y1);
+        let shader = shader_for(fill, bounds, surface.prepared);
+
+        if [x0, y0, x1, y1].iter().all(|v| v.fract() == 0.0) {
+          let area = Region::from_bounds(bounds).intersect(&surface.clip);
+          for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+              surface.plot(x, y, shader.at(x, y), 1.0);
+            }
+          }
+        } else {
+          // fractional edges from non-integer scales get anti-aliased
+          surface.fill_sdf(bounds, &shader, |x, y| rounded_rect_sdf((x, y), (x0, y0), (x1 - x0, y1 - y0), 0.0));
+        }
       },
-      Layer::Line { start, end, width, color } => {
-        let (sx, sy) = (start.0 as f32, start.1 as f32);
-        let (ex, ey) = (end.0 as f32, end.1 as f32);
+      Layer::RoundedRect {
+        size,
+        position,
+        radius,
+        fill,
+        border
+      } => {
+        let min = view.point(*position);
+        let size = (view.len(size.0), view.len(size.1));
+        let bounds = (min.0, min.1, min.0 + size.0, min.1 + size.1);
+        let radius = view.len(*radius);
+
+        surface.fill_sdf(bounds, &shader_for(fill, bounds, surface.prepared), |x, y| {
+          outline(rounded_rect_sdf((x, y), min, size, radius), *border, view)
+        });
+      },
+      Layer::Circle {
+        center,
+        radius,
+        fill,
+        border
+      } => {
+        let (cx, cy) = view.point(*center);
+        let r = view.len(*radius);
+        let bounds = (cx - r, cy - r, cx + r, cy + r);
 
-        for w in 0..*width {
-          let offset = w as f32;
-          draw_line_segment_mut(img, (sx, sy + offset), (ex, ey + offset), *color);
+        surface.fill_sdf(bounds, &shader_for(fill, bounds, surface.prepared), |x, y| {
+          outline((x - cx).hypot(y - cy) - r, *border, view)
+        });
+      },
+      Layer::Ellipse { center, radii, fill, border } => {
+        let center = view.point(*center);
+        let radii = (view.len(radii.0), view.len(radii.1));
+        let bounds = (center.0 - radii.0, center.1 - radii.1, center.0 + radii.0, center.1 + radii.1);
+
+        surface.fill_sdf(bounds, &shader_for(fill, bounds, surface.prepared), |x, y| {
+          outline(ellipse_sdf((x, y), center, radii), *border, view)
+        });
+      },
+      Layer::Polygon { points, fill, border } => {
+        if points.len() < 3 {
+          return
         }
+
+        let points: Vec<_> = points.iter().map(|p| view.point(*p)).collect();
+        let bounds = points_bounds(&points);
+        surface.fill_sdf(bounds, &shader_for(fill, bounds, surface.prepared), |x, y| {
+          outline(polygon_sdf((x, y), &points), *border, view)
+        });
+      },
+      Layer::Line { start, end, width, color } => {
+        let (a, b, half) = line_geometry(*start, *end, *width, view);
+        let (min_x, min_y, max_x, max_y) = points_bounds(&[a, b]);
+        let bounds = (min_x - half, min_y - half, max_x + half, max_y + half);
+
+        surface.fill_sdf(bounds, &Shader::Solid(*color), |x, y| segment_distance((x, y), a, b) - half);
       },
       Layer::Text {
         size,
@@ -75,22 +485,193 @@ impl Layer {
         content,
         font
       } => {
-        let scale = PxScale::from(*size);
+        let scale = PxScale::from(size * view.scale);
         let font = font.to_fontarc();
-        draw_text_mut(img, *color, position.0 as i32, position.1 as i32, scale, &font, content)
+        let (x, y) = view.point(*position);
+        surface.draw_text(*color, (x.round() as i32, y.round() as i32), scale, &font, content)
       },
-      Layer::Image { scale, position, image } => {
-        let (w, h) = image.dimensions();
-        let nw = (w as f32 * scale) as u32;
-        let nh = (h as f32 * scale) as u32;
-        let resized = image.resize_exact(nw, nh, Lanczos3);
+      Layer::Image { image, transform, scale, .. } => {
+        let Some(bounds) = self.bounds() else { return };
+        let out = match surface.prepared.and_then(|p| p.get(&(self as *const Layer as usize))) {
+          Some(prepared) => Cow::Borrowed(prepared),
+          None => Cow::Owned(transform.scaled(view.scale).apply(image, scale * view.scale))
+        };
 
-        overlay(img, &resized, position.0.into(), position.1.into());
+        let (x, y) = view.point((bounds.x, bounds.y));
+        surface.composite(&out, (x.round() as i64, y.round() as i64), 1.0, BlendMode::Normal);
+      },
+      Layer::Group {
+        layers,
+        clip,
+        opacity,
+        blend,
+        ..
+      } => {
+        let Some(shift) = self.group_shift() else { return };
+        let view = view.shift(shift);
+
+        let outer_clip = surface.clip;
+        if let Some(clip) = clip {
+          let (x0, y0) = view.point((clip.x, clip.y));
+          let (x1, y1) = view.point((clip.right(), clip.bottom()));
+          surface.clip = outer_clip.intersect(&Region::from_bounds((x0.round(), y0.round(), x1.round(), y1.round())));
+        }
+
+        if *opacity >= 1.0 && *blend == BlendMode::Normal {
+          for layer in layers {
+            layer.draw(surface, view);
+          }
+        } else {
+          // isolate the group so overlapping children don't blend with each other twice
+          let mut scratch = surface.scratch();
+          let mut inner = surface.layer(&mut scratch);
+          for layer in layers {
+            layer.draw(&mut inner, view);
+          }
+          surface.merge(&scratch, *opacity, *blend);
+        }
+
+        surface.clip = outer_clip;
       }
     }
   }
 }
 
+/// Maps layer coordinates to device pixels, first moving them by `offset` and then multiplying by `scale`
+#[derive(Clone, Copy)]
+pub(crate) struct View {
+  pub(crate) offset: (i32, i32),
+  pub(crate) scale:  f32
+}
+
+impl Default for View {
+  fn default() -> Self { Self { offset: (0, 0), scale: 1.0 } }
+}
+
+impl View {
+  pub(crate) fn scaled(scale: f32) -> Self { Self { offset: (0, 0), scale } }
+
+  fn point(
+    self,
+    p: (i32, i32)
+  ) -> (f32, f32) {
+    self.point_f32((p.0 as f32, p.1 as f32))
+  }
+
+  fn point_f32(
+    self,
+    p: (f32, f32)
+  ) -> (f32, f32) {
+    ((p.0 + self.offset.0 as f32) * self.scale, (p.1 + self.offset.1 as f32) * self.scale)
+  }
+
+  fn len(
+    self,
+    value: u32
+  ) -> f32 {
+    value as f32 * self.scale
+  }
+
+  fn shift(
+    self,
+    by: (i32, i32)
+  ) -> Self {
+    Self {
+      offset: (self.offset.0 + by.0, self.offset.1 + by.1),
+      ..self
+    }
+  }
+}
+
+/// Floats are hashed by their bits, images by their pixels
+impl Hash for Layer {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    std::mem::discriminant(self).hash(state);
+    match self {
+      Layer::Rect { size, position, fill } => (size, position, fill).hash(state),
+      Layer::RoundedRect {
+        size,
+        position,
+        radius,
+        fill,
+        border
+      } => (size, position, radius, fill, border).hash(state),
+      Layer::Circle {
+        center,
+        radius,
+        fill,
+        border
+      } => (center, radius, fill, border).hash(state),
+      Layer::Ellipse { center, radii, fill, border } => (center, radii, fill, border).hash(state),
+      Layer::Polygon { points, fill, border } => (points, fill, border).hash(state),
+      Layer::Line { start, end, width, color } => (start, end, width, color).hash(state),
+      Layer::Text {
+        size,
+        position,
+        color,
+        content,
+        font
+      } => (size.to_bits(), position, color, content, font).hash(state),
+      Layer::Image {
+        scale,
+        position,
+        image,
+        transform
+      } => {
+        hash_image(image, state);
+        (scale.to_bits(), position, transform).hash(state);
+      },
+      Layer::Group {
+        layers,
+        offset,
+        anchor,
+        clip,
+        opacity,
+        blend
+      } => (layers, offset, anchor, clip, opacity.to_bits(), blend).hash(state)
+    }
+  }
+}
+
+fn one() -> f32 { 1.0 }
+
+fn to_f32(points: &[(i32, i32)]) -> Vec<(f32, f32)> { points.iter().map(|p| (p.0 as f32, p.1 as f32)).collect() }
+
+/// Endpoints and half-width of a line, endpoints sit on pixel centers so 1px lines stay crisp
+fn line_geometry(
+  start: (i32, i32),
+  end: (i32, i32),
+  width: u32,
+  view: View
+) -> ((f32, f32), (f32, f32), f32) {
+  let a = view.point_f32((start.0 as f32 + 0.5, start.1 as f32 + 0.5));
+  let b = view.point_f32((end.0 as f32 + 0.5, end.1 as f32 + 0.5));
+  (a, b, width.max(1) as f32 * view.scale / 2.0)
+}
+
+fn shader_for<'a>(
+  fill: &'a Fill,
+  bounds: (f32, f32, f32, f32),
+  prepared: Option<&'a Prepared>
+) -> Shader<'a> {
+  fill.shader((bounds.0, bounds.1), (bounds.2 - bounds.0, bounds.3 - bounds.1), prepared)
+}
+
+/// Applies the optional border width to a filled shape's distance
+fn outline(
+  distance: f32,
+  border: Option<u32>,
+  view: View
+) -> f32 {
+  match border {
+    Some(width) => stroke(distance, view.len(width)),
+    None => distance
+  }
+}
+
 macro_rules! load_font {
   ($path:expr) => {{
     let path = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), $path));
@@ -98,14 +679,15 @@ macro_rules! load_font {
   }};
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Hash)]
 pub enum Font {
   DejaVuSans,
   UbuntuRegular,
   UbuntuBold,
   RobotoRegular,
   RobotoBold,
-  /// Load your own font of choice
+  /// Load your own font of choice, it's read once and kept for later uses<br>
+  /// Scenes only accept font files inside their asset directory, each distinct file is kept until the program exits
   Custom(&'static str)
 }
 
@@ -117,7 +699,25 @@ impl Font {
       Font::UbuntuBold => load_font!("/fonts/ubuntu/Ubuntu-Bold.ttf"),
       Font::RobotoRegular => load_font!("/fonts/roboto/Roboto-Regular.ttf"),
       Font::RobotoBold => load_font!("/fonts/roboto/Roboto-Bold.ttf"),
-      Font::Custom(p) => Self::from_path(p)
+      Font::Custom(p) => {
+        if let Some(font) = CUSTOM_FONTS.lock().expect("failed to acquire lock").get(p) {
+          return font.clone()
+        }
+        let font = Self::from_path(p);
+        CUSTOM_FONTS.lock().expect("failed to acquire lock").insert(p, font.clone());
+        font
+      }
+    }
+  }
+
+  fn name(self) -> &'static str {
+    match self {
+      Font::DejaVuSans => "DejaVuSans",
+      Font::UbuntuRegular => "UbuntuRegular",
+      Font::UbuntuBold => "UbuntuBold",
+      Font::RobotoRegular => "RobotoRegular",
+      Font::RobotoBold => "RobotoBold",
+      Font::Custom(p) => p
     }
   }
 
@@ -127,3 +727,198 @@ impl Font {
     FontArc::try_from_vec(font).unwrap_or_else(|_| panic!("(Asahi) failed to parse font from given path at {path}"))
   }
 }
+
+/// Custom fonts by their path, the ones loaded from scenes are keyed by their resolved path leaked once
+static CUSTOM_FONTS: LazyLock<Mutex<HashMap<&'static str, FontArc>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Bundled fonts are written by name, custom fonts by their path
+impl Serialize for Font {
+  fn serialize<S: Serializer>(
+    &self,
+    serializer: S
+  ) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.name())
+  }
+}
+
+impl<'de> Deserialize<'de> for Font {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    let bundled = [
+      Font::DejaVuSans,
+      Font::UbuntuRegular,
+      Font::UbuntuBold,
+      Font::RobotoRegular,
+      Font::RobotoBold
+    ];
+    if let Some(font) = bundled.into_iter().find(|f| f.name() == name) {
+      return Ok(font)
+    }
+
+    // resolved and parsed up front, so a bad path fails here instead of panicking on the first render
+    let path = scene::asset_path("font", &name).map_err(D::Error::custom)?;
+    let key = path.to_string_lossy().into_owned();
+    if let Some((path, _)) = CUSTOM_FONTS.lock().expect("failed to acquire lock").get_key_value(key.as_str()) {
+      return Ok(Font::Custom(path))
+    }
+    let bytes = std::fs::read(&path).map_err(|e| D::Error::custom(format!("failed to read font {name}: {e}")))?;
+    let font = FontArc::try_from_vec(bytes).map_err(|_| D::Error::custom(format!("`{name}` isn't a valid font file")))?;
+
+    let mut fonts = CUSTOM_FONTS.lock().expect("failed to acquire lock");
+    if let Some((path, _)) = fonts.get_key_value(key.as_str()) {
+      return Ok(Font::Custom(path))
+    }
+    let path: &'static str = Box::leak(key.into_boxed_str());
+    fonts.insert(path, font);
+    Ok(Font::Custom(path))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn blank() -> RgbaImage { RgbaImage::from_pixel(100, 100, Rgba([0, 0, 0, 255])) }
+
+  #[test]
+  fn test_rounded_rect_corners() {
+    let mut img = blank();
+    let color = Rgba([255, 255, 255, 255]);
+    Layer::RoundedRect {
+      size:     (60, 40),
+      position: (20, 30),
+      radius:   12,
+      fill:     color.into(),
+      border:   None
+    }
+    .render(&mut img);
+
+    assert_eq!(*img.get_pixel(50, 50), color);
+    assert_eq!(*img.get_pixel(20, 30), Rgba([0, 0, 0, 255]), "corner should be cut");
+    assert_eq!(*img.get_pixel(50, 30), color, "edge should be filled");
+  }
+
+  #[test]
+  fn test_circle_border_is_hollow() {
+    let mut img = blank();
+    let color = Rgba([255, 0, 0, 255]);
+    Layer::Circle {
+      center: (50, 50),
+      radius: 30,
+      fill:   color.into(),
+      border: Some(4)
+    }
+    .render(&mut img);
+
+    assert_eq!(*img.get_pixel(50, 50), Rgba([0, 0, 0, 255]));
+    assert_eq!(*img.get_pixel(50, 22), color);
+  }
+
+  #[test]
+  fn test_steep_line_keeps_width() {
+    let mut img = blank();
+    let color = Rgba([255, 255, 255, 255]);
+    Layer::Line {
+      start: (50, 10),
+      end: (52, 90),
+      width: 6,
+      color
+    }
+    .render(&mut img);
+
+    let covered = (0..100).filter(|x| img.get_pixel(*x, 50).0[0] > 127).count();
+    assert!((5..=7).contains(&covered), "expected ~6px wide line, got {covered}px");
+  }
+
+  #[test]
+  fn test_polygon_fill() {
+    let mut img = blank();
+    let color = Rgba([0, 255, 0, 255]);
+    Layer::Polygon {
+      points: vec![(10, 90), (50, 10), (90, 90)],
+      fill:   color.into(),
+      border: None
+    }
+    .render(&mut img);
+
+    assert_eq!(*img.get_pixel(50, 70), color);
+    assert_eq!(*img.get_pixel(15, 20), Rgba([0, 0, 0, 255]));
+  }
+
+  #[test]
+  fn test_translucent_rect_blends() {
+    let mut img = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+    Layer::Rect {
+      size:     (10, 10),
+      position: (0, 0),
+      fill:     Rgba([0, 0, 0, 128]).into()
+    }
+    .render(&mut img);
+
+    let px = img.get_pixel(5, 5);
+    assert!((126..=128).contains(&px.0[0]), "expected mid grey, got {px:?}");
+    assert_eq!(px.0[3], 255);
+  }
+
+  #[test]
+  fn test_group_opacity_and_multiply() {
+    let mut img = RgbaImage::from_pixel(10, 10, Rgba([200, 200, 200, 255]));
+    Layer::Rect {
+      size:     (10, 10),
+      position: (0, 0),
+      fill:     Rgba([128, 128, 128, 255]).into()
+    }
+    .with_blend(BlendMode::Multiply)
+    .with_opacity(1.0)
+    .render(&mut img);
+    assert_eq!(img.get_pixel(0, 0).0[0], 100);
+
+    let mut img = RgbaImage::from_pixel(10, 10, Rgba([0, 0, 0, 255]));
+    Layer::Rect {
+      size:     (10, 10),
+      position: (0, 0),
+      fill:     Rgba([255, 255, 255, 255]).into()
+    }
+    .with_opacity(0.25)
+    .render(&mut img);
+    assert_eq!(img.get_pixel(0, 0).0[0], 64);
+  }
+
+  #[test]
+  fn test_negative_position_is_clipped() {
+    let mut img = blank();
+    let color = Rgba([255, 255, 255, 255]);
+    Layer::Rect {
+      size:     (30, 30),
+      position: (-10, -10),
+      fill:     color.into()
+    }
+    .render(&mut img);
+
+    assert_eq!(*img.get_pixel(0, 0), color);
+    assert_eq!(*img.get_pixel(19, 19), color);
+    assert_eq!(*img.get_pixel(20, 20), Rgba([0, 0, 0, 255]));
+  }
+
+  #[test]
+  fn test_group_offset_anchor_and_clip() {
+    let color = Rgba([255, 255, 255, 255]);
+    let square = || Layer::Rect {
+      size:     (20, 20),
+      position: (0, 0),
+      fill:     color.into()
+    };
+
+    let centered = square().anchored(Anchor::Center, (50, 50));
+    assert_eq!(centered.bounds(), Some(Region::new(40, 40, 20, 20)));
+
+    let mut img = blank();
+    Layer::group(vec![square()])
+      .with_offset((10, 10))
+      .with_clip(Region::new(0, 0, 10, 20))
+      .render(&mut img);
+
+    assert_eq!(*img.get_pixel(15, 15), color);
+    assert_eq!(*img.get_pixel(25, 15), Rgba([0, 0, 0, 255]), "right half should be clipped");
+  }
+}
@@ -1,18 +1,70 @@
+mod cache;
 mod canvas;
+mod emote;
+mod fill;
 mod layer;
+mod raster;
+mod rich_text;
+mod scene;
+#[cfg(test)]
+mod snapshot;
+mod svg;
 pub mod templates;
+mod transform;
 mod worker;
 
 pub use {
+  async_trait::async_trait,
   canvas::{
     Canvas,
+    DEFAULT_EMOTE_LIMIT,
+    EmoteSource,
     ImageFormat,
+    clear_render_cache,
     parse_all_emotes,
+    parse_emotes,
+    set_render_cache_size,
     to_rgba
   },
+  emote::{
+    CdnProvider,
+    DirectoryProvider,
+    EmojiSet,
+    EmoteCacheConfig,
+    EmoteCacheStats,
+    EmoteProvider,
+    clear_emote_cache,
+    configure_emote_cache,
+    emote_cache_stats,
+    set_emote_provider
+  },
+  fill::{
+    ColorStop,
+    Fill,
+    ImageFit
+  },
   layer::{
+    Anchor,
     Font,
     Layer
   },
-  worker::prefetch_emotes
+  raster::{
+    BlendMode,
+    Region
+  },
+  rich_text::{
+    RichLine,
+    RichText
+  },
+  scene::SceneFormat,
+  transform::{
+    ImageFilter,
+    ImageMask,
+    ImageTransform,
+    Resample
+  },
+  worker::{
+    prefetch_emotes,
+    prefetch_emotes_await
+  }
 };
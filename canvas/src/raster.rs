@@ -0,0 +1,427 @@
+use {
+  crate::fill::Shader,
+  ab_glyph::{
+    Font,
+    FontArc,
+    GlyphId,
+    PxScale,
+    ScaleFont,
+    point
+  },
+  image::{
+    Pixel,
+    Rgba,
+    RgbaImage
+  },
+  serde::{
+    Deserialize,
+    Serialize
+  },
+  std::collections::HashMap
+};
+
+/// Controls how a layer's colors are mixed with the pixels beneath it
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+  /// Regular alpha compositing
+  #[default]
+  Normal,
+  /// Darkens by multiplying both colors
+  Multiply,
+  /// Lightens by inverting, multiplying and inverting again
+  Screen,
+  /// Multiplies the darks and screens the lights of the backdrop
+  Overlay
+}
+
+impl BlendMode {
+  fn mix(
+    self,
+    backdrop: f32,
+    source: f32
+  ) -> f32 {
+    match self {
+      BlendMode::Normal => source,
+      BlendMode::Multiply => backdrop * source,
+      BlendMode::Screen => backdrop + source - backdrop * source,
+      BlendMode::Overlay => {
+        if backdrop <= 0.5 {
+          2.0 * backdrop * source
+        } else {
+          let b = 2.0 * backdrop - 1.0;
+          b + source - b * source
+        }
+      },
+    }
+  }
+}
+
+/// Composites the source color over the pixel, `alpha` scales the source's own alpha
+pub(crate) fn blend(
+  dst: &mut Rgba<u8>,
+  src: Rgba<u8>,
+  alpha: f32,
+  mode: BlendMode
+) {
+  let sa = src.0[3] as f32 / 255.0 * alpha.clamp(0.0, 1.0);
+  if sa <= 0.0 {
+    return
+  }
+
+  let da = dst.0[3] as f32 / 255.0;
+  let out_a = sa + da * (1.0 - sa);
+
+  for i in 0..3 {
+    let cs = src.0[i] as f32 / 255.0;
+    let cb = dst.0[i] as f32 / 255.0;
+    let mixed = (1.0 - da) * cs + da * mode.mix(cb, cs);
+    let co = (sa * mixed + (1.0 - sa) * da * cb) / out_a;
+    dst.0[i] = (co * 255.0).round().clamp(0.0, 255.0) as u8;
+  }
+
+  dst.0[3] = (out_a * 255.0).round() as u8;
+}
+
+/// Axis-aligned rectangle in canvas pixels, used for clipping and layer bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Region {
+  pub x:      i32,
+  pub y:      i32,
+  pub width:  u32,
+  pub height: u32
+}
+
+impl Region {
+  pub fn new(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32
+  ) -> Self {
+    Self { x, y, width, height }
+  }
+
+  pub fn right(&self) -> i32 { self.x.saturating_add_unsigned(self.width) }
+
+  pub fn bottom(&self) -> i32 { self.y.saturating_add_unsigned(self.height) }
+
+  /// Overlapping area of both regions, empty regions have zero width or height
+  pub fn intersect(
+    &self,
+    other: &Region
+  ) -> Region {
+    let x = self.x.max(other.x);
+    let y = self.y.max(other.y);
+    let right = self.right().min(other.right()).max(x);
+    let bottom = self.bottom().min(other.bottom()).max(y);
+    Region::new(x, y, (right - x) as u32, (bottom - y) as u32)
+  }
+
+  /// Smallest region containing both regions
+  pub fn union(
+    &self,
+    other: &Region
+  ) -> Region {
+    let x = self.x.min(other.x);
+    let y = self.y.min(other.y);
+    let right = self.right().max(other.right());
+    let bottom = self.bottom().max(other.bottom());
+    Region::new(x, y, (right - x) as u32, (bottom - y) as u32)
+  }
+
+  pub fn translate(
+    &self,
+    offset: (i32, i32)
+  ) -> Region {
+    Region::new(self.x + offset.0, self.y + offset.1, self.width, self.height)
+  }
+
+  /// Region covering the float bounds (min x, min y, max x, max y)
+  pub(crate) fn from_bounds(bounds: (f32, f32, f32, f32)) -> Region {
+    let x = bounds.0.floor() as i32;
+    let y = bounds.1.floor() as i32;
+    let right = (bounds.2.ceil() as i32).max(x);
+    let bottom = (bounds.3.ceil() as i32).max(y);
+    Region::new(x, y, (right - x) as u32, (bottom - y) as u32)
+  }
+
+  fn contains(
+    &self,
+    x: i32,
+    y: i32
+  ) -> bool {
+    x >= self.x && y >= self.y && x < self.right() && y < self.bottom()
+  }
+}
+
+/// Images prepared ahead of drawing, keyed by the address of the layer or fill they belong to<br>
+/// Lets the bands of a parallel render share resized images instead of computing them each
+pub(crate) type Prepared = HashMap<usize, RgbaImage>;
+
+/// Pixels being drawn on, restricted to the clip region<br>
+/// Coordinates are in canvas pixels, the buffer may hold only a band of the canvas' rows starting at `top`
+pub(crate) struct Surface<'a> {
+  pixels:              &'a mut [u8],
+  width:               u32,
+  top:                 i32,
+  pub(crate) clip:     Region,
+  pub(crate) prepared: Option<&'a Prepared>
+}
+
+impl<'a> Surface<'a> {
+  pub(crate) fn new(img: &'a mut RgbaImage) -> Self {
+    let (width, height) = img.dimensions();
+    Self::band(img, width, 0, height, None)
+  }
+
+  /// Surface over `rows` rows of a canvas `width` pixels wide, the first of them being row `top`
+  pub(crate) fn band(
+    pixels: &'a mut [u8],
+    width: u32,
+    top: i32,
+    rows: u32,
+    prepared: Option<&'a Prepared>
+  ) -> Self {
+    Self {
+      pixels,
+      width,
+      top,
+      clip: Region::new(0, top, width, rows),
+      prepared
+    }
+  }
+
+  fn pixel_mut(
+    &mut self,
+    x: i32,
+    y: i32
+  ) -> &mut Rgba<u8> {
+    let i = ((y - self.top) as usize * self.width as usize + x as usize) * 4;
+    Rgba::from_slice_mut(&mut self.pixels[i..i + 4])
+  }
+
+  /// Transparent buffer matching this surface, to be drawn on through [Surface::layer]
+  pub(crate) fn scratch(&self) -> Vec<u8> { vec![0; self.pixels.len()] }
+
+  /// Surface over the scratch buffer sharing this surface's position and clip
+  pub(crate) fn layer<'b>(
+    &self,
+    scratch: &'b mut [u8]
+  ) -> Surface<'b>
+  where
+    'a: 'b
+  {
+    Surface {
+      pixels:   scratch,
+      width:    self.width,
+      top:      self.top,
+      clip:     self.clip,
+      prepared: self.prepared
+    }
+  }
+
+  /// Composites a scratch buffer created by [Surface::scratch] back onto the surface
+  pub(crate) fn merge(
+    &mut self,
+    scratch: &[u8],
+    opacity: f32,
+    mode: BlendMode
+  ) {
+    let clip = self.clip;
+    for y in clip.y..clip.bottom() {
+      for x in clip.x..clip.right() {
+        let i = ((y - self.top) as usize * self.width as usize + x as usize) * 4;
+        let px = *Rgba::from_slice(&scratch[i..i + 4]);
+        blend(self.pixel_mut(x, y), px, opacity, mode);
+      }
+    }
+  }
+
+  /// Overwrites every pixel within the clip with the shader's color
+  pub(crate) fn paint(
+    &mut self,
+    shader: &Shader
+  ) {
+    let clip = self.clip;
+    for y in clip.y..clip.bottom() {
+      for x in clip.x..clip.right() {
+        *self.pixel_mut(x, y) = shader.at(x, y);
+      }
+    }
+  }
+
+  /// Blends the color into the pixel by given coverage, pixels outside the clip are ignored
+  pub(crate) fn plot(
+    &mut self,
+    x: i32,
+    y: i32,
+    color: Rgba<u8>,
+    coverage: f32
+  ) {
+    if coverage <= 0.0 || !self.clip.contains(x, y) {
+      return
+    }
+
+    blend(self.pixel_mut(x, y), color, coverage, BlendMode::Normal);
+  }
+
+  /// Composites the source image onto the surface with its top-left corner at given position
+  pub(crate) fn composite(
+    &mut self,
+    src: &RgbaImage,
+    position: (i64, i64),
+    opacity: f32,
+    mode: BlendMode
+  ) {
+    let x0 = position.0.max(self.clip.x as i64);
+    let y0 = position.1.max(self.clip.y as i64);
+    let x1 = (position.0 + src.width() as i64).min(self.clip.right() as i64);
+    let y1 = (position.1 + src.height() as i64).min(self.clip.bottom() as i64);
+
+    for y in y0..y1 {
+      for x in x0..x1 {
+        let px = *src.get_pixel((x - position.0) as u32, (y - position.1) as u32);
+        blend(self.pixel_mut(x as i32, y as i32), px, opacity, mode);
+      }
+    }
+  }
+
+  /// Draws a single line of text with its top-left corner at given position
+  pub(crate) fn draw_text(
+    &mut self,
+    color: Rgba<u8>,
+    position: (i32, i32),
+    scale: PxScale,
+    font: &FontArc,
+    text: &str
+  ) {
+    let scaled = font.as_scaled(scale);
+    let mut caret = 0.0;
+    let mut last: Option<GlyphId> = None;
+
+    for c in text.chars() {
+      let id = scaled.glyph_id(c);
+      if let Some(last) = last {
+        caret += scaled.kern(last, id);
+      }
+
+      let glyph = id.with_scale_and_position(scale, point(caret, scaled.ascent()));
+      caret += scaled.h_advance(id);
+      last = Some(id);
+
+      if let Some(outlined) = font.outline_glyph(glyph) {
+        let bb = outlined.px_bounds();
+        let x = position.0 + bb.min.x.round() as i32;
+        let y = position.1 + bb.min.y.round() as i32;
+        outlined.draw(|gx, gy, coverage| self.plot(x + gx as i32, y + gy as i32, color, coverage));
+      }
+    }
+  }
+
+  /// Rasterizes a shape described by a signed distance function (negative inside, in pixels)<br>
+  /// Only the pixels within `bounds` (min x, min y, max x, max y) are sampled
+  pub(crate) fn fill_sdf(
+    &mut self,
+    bounds: (f32, f32, f32, f32),
+    shader: &Shader,
+    sdf: impl Fn(f32, f32) -> f32
+  ) {
+    let x0 = (bounds.0.floor() as i32 - 1).max(self.clip.x);
+    let y0 = (bounds.1.floor() as i32 - 1).max(self.clip.y);
+    let x1 = (bounds.2.ceil() as i32 + 1).min(self.clip.right());
+    let y1 = (bounds.3.ceil() as i32 + 1).min(self.clip.bottom());
+
+    for y in y0..y1 {
+      for x in x0..x1 {
+        let coverage = (0.5 - sdf(x as f32 + 0.5, y as f32 + 0.5)).clamp(0.0, 1.0);
+        if coverage > 0.0 {
+          self.plot(x, y, shader.at(x, y), coverage);
+        }
+      }
+    }
+  }
+}
+
+/// Turns a filled shape's distance into an inner outline of given width
+pub(crate) fn stroke(
+  distance: f32,
+  width: f32
+) -> f32 {
+  distance.max(-(distance + width))
+}
+
+/// Distance from the point to a line segment
+pub(crate) fn segment_distance(
+  p: (f32, f32),
+  a: (f32, f32),
+  b: (f32, f32)
+) -> f32 {
+  let (pax, pay) = (p.0 - a.0, p.1 - a.1);
+  let (bax, bay) = (b.0 - a.0, b.1 - a.1);
+  let len = bax * bax + bay * bay;
+  let h = if len == 0.0 {
+    0.0
+  } else {
+    ((pax * bax + pay * bay) / len).clamp(0.0, 1.0)
+  };
+
+  (pax - bax * h).hypot(pay - bay * h)
+}
+
+/// Signed distance to a rectangle with rounded corners
+pub(crate) fn rounded_rect_sdf(
+  p: (f32, f32),
+  min: (f32, f32),
+  size: (f32, f32),
+  radius: f32
+) -> f32 {
+  let (hw, hh) = (size.0 / 2.0, size.1 / 2.0);
+  let radius = radius.min(hw).min(hh).max(0.0);
+  let qx = (p.0 - min.0 - hw).abs() - (hw - radius);
+  let qy = (p.1 - min.1 - hh).abs() - (hh - radius);
+
+  qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius
+}
+
+/// Approximated signed distance to an axis-aligned ellipse
+pub(crate) fn ellipse_sdf(
+  p: (f32, f32),
+  center: (f32, f32),
+  radii: (f32, f32)
+) -> f32 {
+  let (rx, ry) = (radii.0.max(0.5), radii.1.max(0.5));
+  let (dx, dy) = (p.0 - center.0, p.1 - center.1);
+  let f = (dx / rx).powi(2) + (dy / ry).powi(2) - 1.0;
+  let grad = (2.0 * dx / (rx * rx)).hypot(2.0 * dy / (ry * ry));
+
+  if grad == 0.0 { -rx.min(ry) } else { f / grad }
+}
+
+/// Signed distance to a polygon, using the even-odd rule for the inside test
+pub(crate) fn polygon_sdf(
+  p: (f32, f32),
+  points: &[(f32, f32)]
+) -> f32 {
+  let mut distance = f32::MAX;
+  let mut inside = false;
+
+  for i in 0..points.len() {
+    let a = points[i];
+    let b = points[(i + 1) % points.len()];
+    distance = distance.min(segment_distance(p, a, b));
+
+    if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+      inside = !inside;
+    }
+  }
+
+  if inside { -distance } else { distance }
+}
+
+/// Bounding box of the points as (min x, min y, max x, max y)
+pub(crate) fn points_bounds(points: &[(f32, f32)]) -> (f32, f32, f32, f32) {
+  points.iter().fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |b, p| {
+    (b.0.min(p.0), b.1.min(p.1), b.2.max(p.0), b.3.max(p.1))
+  })
+}
@@ -0,0 +1,347 @@
+use {
+  crate::{
+    canvas::{
+      EmoteSource,
+      Segment,
+      assume_text_width,
+      split_emotes
+    },
+    emote::cached_emote,
+    layer::{
+      Font,
+      Layer
+    },
+    worker::prefetch_emotes
+  },
+  ab_glyph::{
+    Font as _,
+    PxScale,
+    ScaleFont
+  },
+  image::{
+    DynamicImage,
+    Rgba
+  },
+  unicode_segmentation::UnicodeSegmentation
+};
+
+/// Space left on each side of an inline emote, relative to the font size
+const EMOTE_MARGIN: f32 = 0.06;
+
+/// Single line of text with Discord emotes (`<:name:id>`), emojis and `:shortcode:`s drawn inline as images<br>
+/// Emotes are as tall as the font size and centred on the line, the ones not cached yet are fetched in the
+/// background and drawn once available, emojis fall back to the font meanwhile
+#[derive(Clone)]
+pub struct RichText {
+  pub content:    String,
+  pub size:       f32,
+  pub color:      Rgba<u8>,
+  pub font:       Font,
+  /// Emotes past this many are left out<br>
+  /// Defaults to `None`, every emote is drawn
+  pub max_emotes: Option<usize>
+}
+
+enum Item {
+  Text(String),
+  Emote(DynamicImage)
+}
+
+/// [RichText] with its emotes resolved and every piece measured, made by [RichText::lay_out]<br>
+/// Measuring and drawing the same line keeps the width in step with what ends up on the canvas
+pub struct RichLine {
+  items:  Vec<(Item, f32)>,
+  emotes: usize,
+  size:   f32,
+  color:  Rgba<u8>,
+  font:   Font
+}
+
+impl RichText {
+  pub fn new(
+    content: impl Into<String>,
+    size: f32,
+    color: Rgba<u8>,
+    font: Font
+  ) -> Self {
+    Self {
+      content: content.into(),
+      size,
+      color,
+      font,
+      max_emotes: None
+    }
+  }
+
+  pub fn with_max_emotes(
+    mut self,
+    limit: usize
+  ) -> Self {
+    self.max_emotes = Some(limit);
+    self
+  }
+
+  /// Width of the laid out line in canvas pixels, use [RichText::lay_out] to also draw it
+  pub fn width(&self) -> u32 { self.lay_out().width() }
+
+  /// Group of text and image layers with the top left of the line at given position
+  pub fn layer(
+    &self,
+    position: (i32, i32)
+  ) -> Layer {
+    self.lay_out().layer(position)
+  }
+
+  /// Resolves the emotes and measures every piece of the line<br>
+  /// Emotes that aren't cached yet are queued for fetching, use [RichLine::truncated] to fit it in a width
+  pub fn lay_out(&self) -> RichLine {
+    let font = self.font.to_fontarc();
+    let margin = self.size * EMOTE_MARGIN;
+    let limit = self.max_emotes.unwrap_or(usize::MAX);
+    let mut emotes_left = limit;
+    let mut items = Vec::new();
+    let mut misses = Vec::new();
+
+    for segment in split_emotes(&self.content) {
+      let (emote, raw) = match segment {
+        Segment::Text(text) => {
+          items.push((Item::Text(text.to_string()), assume_text_width(text, self.size, &font) as f32));
+          continue
+        },
+        Segment::Emote(emote, raw) => (emote, raw)
+      };
+      if emotes_left == 0 {
+        continue
+      }
+      emotes_left -= 1;
+
+      match cached_emote(&emote) {
+        Some(image) => {
+          let width = image.width() as f32 * self.size / image.height().max(1) as f32;
+          items.push((Item::Emote(image), width + margin * 2.0));
+        },
+        None => {
+          if let EmoteSource::Unicode(emoji) = &emote {
+            let text = if raw.starts_with(':') { emoji.clone() } else { raw.to_string() };
+            let width = assume_text_width(&text, self.size, &font) as f32;
+            items.push((Item::Text(text), width));
+          }
+          misses.push(emote);
+        }
+      }
+    }
+    prefetch_emotes(misses);
+
+    RichLine {
+      items,
+      emotes: limit - emotes_left,
+      size: self.size,
+      color: self.color,
+      font: self.font
+    }
+  }
+}
+
+impl RichLine {
+  /// Width of the line in canvas pixels
+  pub fn width(&self) -> u32 { self.items.iter().map(|(_, width)| width).sum::<f32>().ceil() as u32 }
+
+  /// Emotes taken from the content, counting the ones still being fetched
+  pub fn emotes(&self) -> usize { self.emotes }
+
+  /// Shortens the line with an ellipsis so it fits within `max_width`<br>
+  /// Text is cut between graphemes and emotes are kept or dropped whole, so their tags are never split
+  pub fn truncated(
+    mut self,
+    max_width: u32
+  ) -> Self {
+    if self.width() <= max_width {
+      return self
+    }
+
+    let font = self.font.to_fontarc();
+    let budget = max_width.saturating_sub(assume_text_width("…", self.size, &font)) as f32;
+    let mut used = 0.0;
+    let mut kept = Vec::new();
+    for (item, width) in self.items {
+      if used + width <= budget {
+        used += width;
+        kept.push((item, width));
+        continue
+      }
+      if let Item::Text(content) = item {
+        let mut cut = String::new();
+        for grapheme in content.graphemes(true) {
+          cut.push_str(grapheme);
+          if used + assume_text_width(&cut, self.size, &font) as f32 > budget {
+            cut.truncate(cut.len() - grapheme.len());
+            break
+          }
+        }
+        kept.push((Item::Text(cut), 0.0));
+      }
+      break
+    }
+
+    // the ellipsis joins the last piece of text, or follows the emote the line was cut after
+    match kept.last_mut() {
+      Some((Item::Text(content), width)) => {
+        *content = format!("{}…", content.trim_end());
+        *width = assume_text_width(content, self.size, &font) as f32;
+      },
+      _ => kept.push((Item::Text("…".to_string()), assume_text_width("…", self.size, &font) as f32))
+    }
+    self.items = kept;
+    self
+  }
+
+  /// Group of text and image layers with the top left of the line at given position
+  pub fn layer(
+    &self,
+    position: (i32, i32)
+  ) -> Layer {
+    let line_height = self.font.to_fontarc().as_scaled(PxScale::from(self.size)).height();
+    let emote_y = position.1 + ((line_height - self.size) / 2.0).round() as i32;
+    let margin = self.size * EMOTE_MARGIN;
+    let mut caret = position.0 as f32;
+    let mut layers = Vec::new();
+
+    for (item, width) in &self.items {
+      match item {
+        Item::Text(content) => layers.push(Layer::Text {
+          size:     self.size,
+          position: (caret.round() as i32, position.1),
+          color:    self.color,
+          content:  content.clone(),
+          font:     self.font
+        }),
+        Item::Emote(image) => {
+          let scale = self.size / image.height().max(1) as f32;
+          layers.push(Layer::Image {
+            scale,
+            position: ((caret + margin).round() as i32, emote_y),
+            image: image.clone(),
+            transform: Default::default()
+          });
+        }
+      }
+      caret += width;
+    }
+
+    Layer::group(layers)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::{
+      Canvas,
+      emote::{
+        EmoteProvider,
+        is_emote_cached,
+        use_test_provider
+      },
+      snapshot::{
+        assert_snapshot,
+        stub_emotes
+      }
+    },
+    asahi_internal::AsahiResult,
+    async_trait::async_trait,
+    image::{
+      ImageFormat,
+      RgbaImage
+    },
+    std::{
+      io::Cursor,
+      thread,
+      time::{
+        Duration,
+        Instant
+      }
+    }
+  };
+
+  /// Serves the same square for every emote
+  struct Square;
+
+  #[async_trait]
+  impl EmoteProvider for Square {
+    async fn fetch(
+      &self,
+      _emote: &EmoteSource
+    ) -> AsahiResult<Vec<u8>> {
+      let mut bytes = Vec::new();
+      DynamicImage::ImageRgba8(RgbaImage::new(8, 8))
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+      Ok(bytes)
+    }
+  }
+
+  #[test]
+  fn test_inline_emotes() {
+    let content = "gg <:pog:501> 🔥 wp :floppy_disk: 👨‍👩‍👧";
+    stub_emotes(content);
+    let text = RichText::new(content, 32.0, Rgba([255, 255, 255, 255]), Font::UbuntuRegular);
+    let plain = assume_text_width("gg  wp  ", 32.0, &Font::UbuntuRegular.to_fontarc());
+    assert!(text.width() > plain + 4 * 32);
+    assert!(text.clone().with_max_emotes(1).width() < text.width());
+    assert_eq!(text.lay_out().emotes(), 4);
+    assert_eq!(text.clone().with_max_emotes(1).lay_out().emotes(), 1);
+
+    let mut canvas = Canvas::new(text.width() + 20, 60);
+    canvas.set_bg_color(Rgba([30, 30, 30, 255]));
+    canvas.add_layer(text.layer((10, 12)));
+    assert_snapshot("rich_text", &canvas);
+  }
+
+  #[test]
+  fn test_uncached_emote() {
+    let _provider = use_test_provider(Square);
+    let emote = EmoteSource::Discord("900201".into());
+    let text = RichText::new("hi <:new:900201>", 32.0, Rgba([255, 255, 255, 255]), Font::UbuntuRegular);
+    let line = text.lay_out();
+    let _ = line.layer((0, 0));
+    assert_eq!(line.width(), assume_text_width("hi ", 32.0, &Font::UbuntuRegular.to_fontarc()));
+
+    let started = Instant::now();
+    while !is_emote_cached(&emote) {
+      assert!(started.elapsed() < Duration::from_secs(5), "emote was never fetched");
+      thread::sleep(Duration::from_millis(10));
+    }
+    assert!(text.width() > line.width());
+  }
+
+  #[test]
+  fn test_truncated() {
+    let content = "gg <:pog:501> well played everyone";
+    stub_emotes(content);
+    let text = RichText::new(content, 32.0, Rgba([255, 255, 255, 255]), Font::UbuntuRegular);
+    let font = Font::UbuntuRegular.to_fontarc();
+    let full = text.lay_out();
+    let full_width = full.width();
+    assert_eq!(full.truncated(full_width).width(), full_width);
+
+    // cutting right after the emote keeps it whole instead of spilling part of its tag as text
+    let upto_emote = assume_text_width("gg ", 32.0, &font) + 32 + assume_text_width("…", 32.0, &font) + 4;
+    let line = text.lay_out().truncated(upto_emote);
+    assert!(line.width() <= upto_emote);
+    assert!(line.items.iter().any(|(item, _)| matches!(item, Item::Emote(_))));
+    assert!(
+      line
+        .items
+        .iter()
+        .all(|(item, _)| !matches!(item, Item::Text(text) if text.contains('<') || text.contains(':')))
+    );
+
+    let line = text.lay_out().truncated(assume_text_width("gg …", 32.0, &font));
+    assert!(line.items.iter().all(|(item, _)| matches!(item, Item::Text(_))));
+    assert_eq!(
+      line.items.last().map(|(item, _)| matches!(item, Item::Text(text) if text.ends_with('…'))),
+      Some(true)
+    );
+  }
+}
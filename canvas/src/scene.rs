@@ -0,0 +1,598 @@
+use {
+  crate::canvas::Canvas,
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
+  },
+  serde_json::Value,
+  std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{
+      Component,
+      Path,
+      PathBuf
+    }
+  }
+};
+
+thread_local! {
+  /// Directory the image paths of the scene being loaded are resolved in, only data URIs are accepted if `None`
+  static ASSET_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Format of a scene file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneFormat {
+  Json,
+  Toml
+}
+
+impl SceneFormat {
+  /// Guesses the format from the file extension
+  pub fn from_path(path: &Path) -> Option<Self> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+      "json" => Some(Self::Json),
+      "toml" => Some(Self::Toml),
+      _ => None
+    }
+  }
+}
+
+impl Canvas {
+  /// Builds a canvas from a JSON or TOML scene, replacing every `{{key}}` in its strings with the value from `data`<br>
+  /// Substituted values stay strings, numeric and boolean fields accept them, so `"width": "{{width}}"` works as well<br>
+  /// Layers and fills are keyed by their kind, e.g. `{ "rect": { "size": [10, 10], "position": [0, 0], "fill": { "solid": "#FF0000" } } }`<br>
+  /// Images must be data URIs, use [Canvas::from_scene_with_assets] to load them from files
+  pub fn from_scene(
+    source: &str,
+    format: SceneFormat,
+    data: &HashMap<String, String>
+  ) -> AsahiResult<Self> {
+    let mut value: Value = match format {
+      SceneFormat::Json => serde_json::from_str(source)?,
+      SceneFormat::Toml => toml::from_str(source).map_err(|e| AsahiError::Parse(format!("invalid TOML scene: {e}").into()))?
+    };
+    substitute(&mut value, data);
+
+    serde_path_to_error::deserialize(lenient::Lenient(value)).map_err(|e| {
+      let path = e.path().to_string();
+      AsahiError::Parse(format!("invalid scene at `{path}`: {}", e.into_inner()).into())
+    })
+  }
+
+  /// Same as [Canvas::from_scene], images may also be paths relative to `assets`<br>
+  /// Absolute paths and ones stepping out of the directory with `..` are rejected
+  pub fn from_scene_with_assets(
+    source: &str,
+    format: SceneFormat,
+    data: &HashMap<String, String>,
+    assets: impl AsRef<Path>
+  ) -> AsahiResult<Self> {
+    let previous = ASSET_DIR.replace(Some(assets.as_ref().to_path_buf()));
+    let canvas = Self::from_scene(source, format, data);
+    ASSET_DIR.set(previous);
+    canvas
+  }
+
+  /// Reads a scene file, the format is picked by its extension (`.json` or `.toml`)<br>
+  /// Image paths are resolved in the scene's directory, see [Canvas::from_scene_with_assets]
+  pub fn load_scene(
+    path: impl AsRef<Path>,
+    data: &HashMap<String, String>
+  ) -> AsahiResult<Self> {
+    let path = path.as_ref();
+    let format = SceneFormat::from_path(path).ok_or_else(|| AsahiError::Parse(format!("unknown scene format for {}", path.display()).into()))?;
+    let source = std::fs::read_to_string(path).map_err(|e| AsahiError::External(format!("failed to read scene {}: {e}", path.display()).into()))?;
+
+    Self::from_scene_with_assets(&source, format, data, path.parent().unwrap_or(Path::new(".")))
+  }
+}
+
+/// Resolved path of an image or font (`kind`) referenced by the scene, which has to exist inside the asset directory<br>
+/// Symlinks are followed before the check, so they can't point outside of it either
+pub(crate) fn asset_path(
+  kind: &str,
+  raw: &str
+) -> Result<PathBuf, String> {
+  let Some(dir) = ASSET_DIR.with_borrow(Clone::clone) else {
+    return Err(format!(
+      "{kind} path `{raw}` needs an asset directory, see Canvas::from_scene_with_assets"
+    ))
+  };
+  let relative = Path::new(raw);
+  if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+    return Err(format!("{kind} path `{raw}` must be relative and stay inside the asset directory"))
+  }
+
+  let dir = dir
+    .canonicalize()
+    .map_err(|e| format!("failed to open asset directory {}: {e}", dir.display()))?;
+  let path = dir
+    .join(relative)
+    .canonicalize()
+    .map_err(|e| format!("failed to read {kind} {raw}: {e}"))?;
+  if !path.starts_with(&dir) {
+    return Err(format!("{kind} path `{raw}` leads outside the asset directory"))
+  }
+  Ok(path)
+}
+
+/// Replaces the placeholders in every string of the value
+fn substitute(
+  value: &mut Value,
+  data: &HashMap<String, String>
+) {
+  match value {
+    Value::String(s) => {
+      let mut out = String::with_capacity(s.len());
+      let mut rest = s.as_str();
+      while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        let key = rest[start + 2..start + end].trim();
+        out.push_str(&rest[..start]);
+        match data.get(key) {
+          Some(replacement) => out.push_str(replacement),
+          None => out.push_str(&rest[start..start + end + 2])
+        }
+        rest = &rest[start + end + 2..];
+      }
+      out.push_str(rest);
+      *s = out;
+    },
+    Value::Array(items) => items.iter_mut().for_each(|v| substitute(v, data)),
+    Value::Object(map) => map.values_mut().for_each(|v| substitute(v, data)),
+    _ => {}
+  }
+}
+
+/// Scene values where numbers and booleans may also be written as strings, as placeholders are
+mod lenient {
+  use {
+    serde::{
+      Deserializer,
+      de::{
+        DeserializeSeed,
+        EnumAccess,
+        Error as _,
+        IntoDeserializer,
+        MapAccess,
+        SeqAccess,
+        Unexpected,
+        VariantAccess,
+        Visitor
+      },
+      forward_to_deserialize_any
+    },
+    serde_json::{
+      Error,
+      Map,
+      Value
+    }
+  };
+
+  pub(super) struct Lenient(pub(super) Value);
+
+  macro_rules! number {
+    ($($method:ident => $parse:ty, $visit:ident);* $(;)?) => {
+      $(
+        fn $method<V: Visitor<'de>>(
+          self,
+          visitor: V
+        ) -> Result<V::Value, Error> {
+          match self.0 {
+            Value::String(s) => match s.trim().parse::<$parse>() {
+              Ok(n) => visitor.$visit(n),
+              Err(_) => Err(Error::invalid_type(Unexpected::Str(&s), &visitor))
+            },
+            other => other.$method(visitor)
+          }
+        }
+      )*
+    };
+  }
+
+  impl<'de> Deserializer<'de> for Lenient {
+    type Error = Error;
+
+    number! {
+      deserialize_i8 => i64, visit_i64;
+      deserialize_i16 => i64, visit_i64;
+      deserialize_i32 => i64, visit_i64;
+      deserialize_i64 => i64, visit_i64;
+      deserialize_u8 => u64, visit_u64;
+      deserialize_u16 => u64, visit_u64;
+      deserialize_u32 => u64, visit_u64;
+      deserialize_u64 => u64, visit_u64;
+      deserialize_f32 => f64, visit_f64;
+      deserialize_f64 => f64, visit_f64;
+      deserialize_bool => bool, visit_bool
+    }
+
+    forward_to_deserialize_any! {
+      char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(
+      self,
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      match self.0 {
+        Value::Array(items) => visitor.visit_seq(Seq(items.into_iter())),
+        Value::Object(map) => visitor.visit_map(Entries {
+          entries: map.into_iter(),
+          value:   None
+        }),
+        other => other.deserialize_any(visitor)
+      }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+      self,
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      match self.0 {
+        Value::Null => visitor.visit_none(),
+        value => visitor.visit_some(Lenient(value))
+      }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+      self,
+      _name: &'static str,
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+      self,
+      _name: &'static str,
+      _variants: &'static [&'static str],
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      match self.0 {
+        Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+        Value::Object(map) if map.len() == 1 => {
+          let (variant, value) = map.into_iter().next().expect("map has one entry");
+          visitor.visit_enum(Variant { variant, value })
+        },
+        other => Err(Error::invalid_type(unexpected(&other), &"enum variant as string or single key map"))
+      }
+    }
+  }
+
+  fn unexpected(value: &Value) -> Unexpected<'_> {
+    match value {
+      Value::Null => Unexpected::Unit,
+      Value::Bool(b) => Unexpected::Bool(*b),
+      Value::Number(_) => Unexpected::Other("number"),
+      Value::String(s) => Unexpected::Str(s),
+      Value::Array(_) => Unexpected::Seq,
+      Value::Object(_) => Unexpected::Map
+    }
+  }
+
+  struct Seq(std::vec::IntoIter<Value>);
+
+  impl<'de> SeqAccess<'de> for Seq {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+      &mut self,
+      seed: T
+    ) -> Result<Option<T::Value>, Error> {
+      self.0.next().map(|value| seed.deserialize(Lenient(value))).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.0.len()) }
+  }
+
+  struct Entries {
+    entries: <Map<String, Value> as IntoIterator>::IntoIter,
+    value:   Option<Value>
+  }
+
+  impl<'de> MapAccess<'de> for Entries {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+      &mut self,
+      seed: K
+    ) -> Result<Option<K::Value>, Error> {
+      let Some((key, value)) = self.entries.next() else { return Ok(None) };
+      self.value = Some(value);
+      seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+      &mut self,
+      seed: V
+    ) -> Result<V::Value, Error> {
+      let value = self.value.take().ok_or_else(|| Error::custom("value is missing"))?;
+      seed.deserialize(Lenient(value))
+    }
+  }
+
+  struct Variant {
+    variant: String,
+    value:   Value
+  }
+
+  impl<'de> EnumAccess<'de> for Variant {
+    type Error = Error;
+    type Variant = Lenient;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+      self,
+      seed: V
+    ) -> Result<(V::Value, Lenient), Error> {
+      let variant = seed.deserialize(self.variant.into_deserializer())?;
+      Ok((variant, Lenient(self.value)))
+    }
+  }
+
+  impl<'de> VariantAccess<'de> for Lenient {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+      match self.0 {
+        Value::Null => Ok(()),
+        other => Err(Error::invalid_type(unexpected(&other), &"unit variant"))
+      }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+      self,
+      seed: T
+    ) -> Result<T::Value, Error> {
+      seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+      self,
+      _len: usize,
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+      self,
+      _fields: &'static [&'static str],
+      visitor: V
+    ) -> Result<V::Value, Error> {
+      self.deserialize_any(visitor)
+    }
+  }
+}
+
+/// Colors as `#RRGGBB` or `#RRGGBBAA` hex strings
+pub(crate) mod color {
+  use {
+    image::Rgba,
+    serde::{
+      Deserialize,
+      Deserializer,
+      Serializer,
+      de::Error
+    }
+  };
+
+  pub(crate) fn serialize<S: Serializer>(
+    color: &Rgba<u8>,
+    serializer: S
+  ) -> Result<S::Ok, S::Error> {
+    let [r, g, b, a] = color.0;
+    if a == 255 {
+      serializer.serialize_str(&format!("#{r:02X}{g:02X}{b:02X}"))
+    } else {
+      serializer.serialize_str(&format!("#{r:02X}{g:02X}{b:02X}{a:02X}"))
+    }
+  }
+
+  pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgba<u8>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    parse(&raw).ok_or_else(|| D::Error::custom(format!("invalid color `{raw}`, expected #RRGGBB or #RRGGBBAA")))
+  }
+
+  fn parse(raw: &str) -> Option<Rgba<u8>> {
+    let hex = raw.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+      return None
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, alpha]))
+  }
+}
+
+/// Images as base64 PNG data URIs, paths inside the asset directory are accepted when loading
+pub(crate) mod image_data {
+  use {
+    base64::{
+      Engine,
+      engine::general_purpose::STANDARD
+    },
+    image::{
+      DynamicImage,
+      ImageFormat
+    },
+    serde::{
+      Deserialize,
+      Deserializer,
+      Serializer,
+      de::Error as _,
+      ser::Error as _
+    },
+    std::io::Cursor
+  };
+
+  /// Encodes the image as a PNG data URI
+  pub(crate) fn to_data_uri(image: &DynamicImage) -> Result<String, image::ImageError> {
+    let mut buf = Vec::new();
+    image.write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(buf)))
+  }
+
+  pub(crate) fn serialize<S: Serializer>(
+    image: &DynamicImage,
+    serializer: S
+  ) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_data_uri(image).map_err(S::Error::custom)?)
+  }
+
+  pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DynamicImage, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+
+    if let Some(data) = raw.strip_prefix("data:") {
+      let (_, encoded) = data
+        .split_once(";base64,")
+        .ok_or_else(|| D::Error::custom("image data URI must be base64 encoded"))?;
+      let bytes = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+      return image::load_from_memory(&bytes).map_err(D::Error::custom)
+    }
+
+    let path = super::asset_path("image", &raw).map_err(D::Error::custom)?;
+    image::open(&path).map_err(|e| D::Error::custom(format!("failed to load image {raw}: {e}")))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::{
+      Fill,
+      Layer
+    },
+    image::Rgba
+  };
+
+  const SCENE: &str = r##"{
+    "width": 200,
+    "height": "{{height}}",
+    "background": { "solid": "#101010" },
+    "layers": [
+      { "rect": { "size": [200, 40], "position": [0, 0], "fill": { "solid": "#FF000080" } } },
+      { "text": { "size": 18.0, "position": [10, 10], "color": "#FFFFFF", "content": "Hello {{name}}!", "font": "UbuntuBold" } }
+    ]
+  }"##;
+
+  fn data() -> HashMap<String, String> { HashMap::from([("height".into(), "100".into()), ("name".into(), "Asahi".into())]) }
+
+  #[test]
+  fn test_json_scene() {
+    let canvas = Canvas::from_scene(SCENE, SceneFormat::Json, &data()).expect("scene should parse");
+
+    assert_eq!((canvas.width, canvas.height), (200, 100));
+    assert!(matches!(canvas.background, Fill::Solid(Rgba([16, 16, 16, 255]))));
+    assert!(matches!(&canvas.layers[1], Layer::Text { content, .. } if content == "Hello Asahi!"));
+  }
+
+  #[test]
+  fn test_toml_scene() {
+    let source = r##"
+      width = 64
+      height = 64
+
+      [[layers]]
+      circle = { center = [32, 32], radius = "{{radius}}", fill = { solid = "#00FF00" } }
+    "##;
+    let canvas = Canvas::from_scene(source, SceneFormat::Toml, &HashMap::from([("radius".into(), "20".into())])).unwrap();
+
+    assert!(matches!(canvas.layers[0], Layer::Circle { radius: 20, .. }));
+  }
+
+  #[test]
+  fn test_error_points_at_field() {
+    let broken = SCENE.replace("#FFFFFF", "white");
+    let Err(err) = Canvas::from_scene(&broken, SceneFormat::Json, &data()) else {
+      panic!("scene with invalid color should fail")
+    };
+    let err = err.to_string();
+
+    assert!(err.contains("layers[1].text.color"), "unexpected error: {err}");
+  }
+
+  #[test]
+  fn test_placeholders_stay_strings() {
+    let source = r##"{
+      "width": "{{size}}",
+      "height": "{{size}}",
+      "layers": [
+        { "text": { "size": "{{size}}", "position": ["{{x}}", 4], "color": "#FFFFFF", "content": "{{size}}", "font": "UbuntuBold" } },
+        { "group": { "layers": [], "opacity": "{{opacity}}" } }
+      ]
+    }"##;
+    let data = HashMap::from([("size".into(), "32".into()), ("x".into(), "-8".into()), ("opacity".into(), "0.5".into())]);
+    let canvas = Canvas::from_scene(source, SceneFormat::Json, &data).expect("placeholders should fill numeric fields");
+
+    assert_eq!((canvas.width, canvas.height), (32, 32));
+    assert!(matches!(&canvas.layers[0], Layer::Text { size: 32.0, position: (-8, 4), content, .. } if content == "32"));
+    assert!(matches!(canvas.layers[1], Layer::Group { opacity: 0.5, .. }));
+    assert!(Canvas::from_scene(&source.replace("\"{{x}}\"", "\"left\""), SceneFormat::Json, &data).is_err());
+  }
+
+  #[test]
+  fn test_image_paths() {
+    let dir = std::env::temp_dir().join(format!("asahi_scene_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    image::RgbaImage::new(4, 4).save(dir.join("dot.png")).unwrap();
+    let scene = |path: &str| format!(r#"{{ "width": 8, "height": 8, "layers": [{{ "image": {{ "position": [0, 0], "image": "{path}" }} }}] }}"#);
+
+    assert!(Canvas::from_scene(&scene("dot.png"), SceneFormat::Json, &HashMap::new()).is_err());
+    assert!(Canvas::from_scene_with_assets(&scene("dot.png"), SceneFormat::Json, &HashMap::new(), &dir).is_ok());
+    for escape in ["../dot.png", "sub/../../dot.png", "/etc/hostname"] {
+      assert!(Canvas::from_scene_with_assets(&scene(escape), SceneFormat::Json, &HashMap::new(), &dir).is_err());
+    }
+
+    std::fs::write(dir.join("scene.json"), scene("./dot.png")).unwrap();
+    assert!(Canvas::load_scene(dir.join("scene.json"), &HashMap::new()).is_ok());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_font_paths() {
+    let dir = std::env::temp_dir().join(format!("asahi_scene_fonts_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSans.ttf"), dir.join("custom.ttf")).unwrap();
+    std::fs::write(dir.join("notes.ttf"), "not a font").unwrap();
+    let scene = |font: &str| {
+      format!(
+        r##"{{ "width": 8, "height": 8, "layers": [{{ "text": {{ "size": 8, "position": [0, 0], "color": "#FFFFFF", "content": "a", "font": "{font}" }} }}] }}"##
+      )
+    };
+    let load = |font: &str| Canvas::from_scene_with_assets(&scene(font), SceneFormat::Json, &HashMap::new(), &dir);
+
+    let canvas = load("custom.ttf").expect("font inside the asset directory should load");
+    assert!(matches!(
+      canvas.layers[0],
+      Layer::Text {
+        font: crate::Font::Custom(_),
+        ..
+      }
+    ));
+    canvas.render();
+    assert!(matches!(load("./custom.ttf").unwrap().layers[0], Layer::Text { font: crate::Font::Custom(path), .. } if path.ends_with("custom.ttf")));
+
+    for broken in ["missing.ttf", "../custom.ttf", "notes.ttf"] {
+      let Err(AsahiError::Parse(err)) = load(broken) else {
+        panic!("font `{broken}` should fail to parse")
+      };
+      assert!(err.contains("layers[0].text.font"), "unexpected error: {err}");
+    }
+    assert!(Canvas::from_scene(&scene("custom.ttf"), SceneFormat::Json, &HashMap::new()).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let canvas = Canvas::from_scene(SCENE, SceneFormat::Json, &data()).unwrap();
+    let json = serde_json::to_string(&canvas).unwrap();
+    let again = Canvas::from_scene(&json, SceneFormat::Json, &HashMap::new()).unwrap();
+
+    assert_eq!(canvas.render().to_rgba8(), again.render().to_rgba8());
+  }
+}
@@ -0,0 +1,161 @@
+// Golden-image testing for templates, renders are compared against the reference PNGs in `tests/snapshots`
+// Run the tests with `ASAHI_BLESS=1` to write new references after an intended visual change
+
+use {
+  crate::{
+    canvas::{
+      Canvas,
+      EmoteSource,
+      parse_emotes
+    },
+    emote::EMOTE_CACHE
+  },
+  image::{
+    DynamicImage,
+    Rgba,
+    RgbaImage
+  },
+  std::path::PathBuf
+};
+
+/// Env var that overwrites the references with the current renders instead of comparing them
+const BLESS_VAR: &str = "ASAHI_BLESS";
+
+/// YIQ distance (0.0 to 1.0) above which two pixels are considered different
+const PIXEL_THRESHOLD: f32 = 0.1;
+
+/// Share of differing pixels tolerated before a snapshot fails
+const MAX_DIFF_RATIO: f64 = 0.001;
+
+/// Largest possible YIQ distance, between black and white
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+fn snapshot_dir() -> PathBuf { PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots") }
+
+/// Renders the canvas and compares it against the reference named `name`<br>
+/// On failure the render and a diff highlighting mismatched pixels in red are written next to the reference
+#[track_caller]
+pub(crate) fn assert_snapshot(
+  name: &str,
+  canvas: &Canvas
+) {
+  let actual = canvas.render().to_rgba8();
+  let dir = snapshot_dir();
+  let reference = dir.join(format!("{name}.png"));
+  let actual_path = dir.join(format!("{name}.actual.png"));
+  let diff_path = dir.join(format!("{name}.diff.png"));
+
+  if std::env::var_os(BLESS_VAR).is_some() {
+    std::fs::create_dir_all(&dir).expect("failed to create snapshot directory");
+    actual.save(&reference).expect("failed to write snapshot");
+    let _ = std::fs::remove_file(&actual_path);
+    let _ = std::fs::remove_file(&diff_path);
+    return
+  }
+
+  let expected = match image::open(&reference) {
+    Ok(expected) => expected.to_rgba8(),
+    Err(e) => panic!("missing snapshot {}: {e}, run with {BLESS_VAR}=1 to create it", reference.display())
+  };
+
+  if expected.dimensions() != actual.dimensions() {
+    actual.save(&actual_path).expect("failed to write render");
+    panic!(
+      "snapshot `{name}` is {:?} but the render is {:?}, see {}",
+      expected.dimensions(),
+      actual.dimensions(),
+      actual_path.display()
+    );
+  }
+
+  let (diff, mismatched) = compare(&expected, &actual);
+  let ratio = mismatched as f64 / (actual.width() as f64 * actual.height() as f64).max(1.0);
+  if ratio > MAX_DIFF_RATIO {
+    actual.save(&actual_path).expect("failed to write render");
+    diff.save(&diff_path).expect("failed to write diff");
+    panic!(
+      "snapshot `{name}` differs in {mismatched} pixels ({:.3}%), see {}",
+      ratio * 100.0,
+      diff_path.display()
+    );
+  }
+
+  let _ = std::fs::remove_file(&actual_path);
+  let _ = std::fs::remove_file(&diff_path);
+}
+
+/// Counts the perceptually different pixels and draws them red over a faded copy of the reference
+fn compare(
+  expected: &RgbaImage,
+  actual: &RgbaImage
+) -> (RgbaImage, usize) {
+  let mut mismatched = 0;
+  let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+    let (a, b) = (expected.get_pixel(x, y), actual.get_pixel(x, y));
+    if yiq_delta(*a, *b) > MAX_YIQ_DELTA * PIXEL_THRESHOLD * PIXEL_THRESHOLD {
+      mismatched += 1;
+      Rgba([255, 0, 0, 255])
+    } else {
+      let l = (255.0 - (255.0 - luma(*a)) * 0.1) as u8;
+      Rgba([l, l, l, 255])
+    }
+  });
+
+  (diff, mismatched)
+}
+
+/// Flattens the pixel onto white
+fn flatten(px: Rgba<u8>) -> [f32; 3] {
+  let a = px.0[3] as f32 / 255.0;
+  [0, 1, 2].map(|i| 255.0 + (px.0[i] as f32 - 255.0) * a)
+}
+
+fn luma(px: Rgba<u8>) -> f32 {
+  let [r, g, b] = flatten(px);
+  r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_2
+}
+
+/// Squared color distance in the YIQ space, weighted towards brightness like the eye is
+fn yiq_delta(
+  a: Rgba<u8>,
+  b: Rgba<u8>
+) -> f32 {
+  let ([r1, g1, b1], [r2, g2, b2]) = (flatten(a), flatten(b));
+  let y = (r1 - r2) * 0.298_895_3 + (g1 - g2) * 0.586_622_5 + (b1 - b2) * 0.114_482_2;
+  let i = (r1 - r2) * 0.595_978 - (g1 - g2) * 0.274_176_1 - (b1 - b2) * 0.321_801_9;
+  let q = (r1 - r2) * 0.211_470_2 - (g1 - g2) * 0.522_617_1 + (b1 - b2) * 0.311_146_9;
+
+  0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+/// Fills the emote cache with placeholder images for every emote in the text so templates never hit the network
+pub(crate) fn stub_emotes(text: &str) {
+  let mut cache = EMOTE_CACHE.lock().expect("failed to acquire lock");
+
+  for emote in parse_emotes(text, None) {
+    let size = match emote {
+      EmoteSource::Discord(_) => 96,
+      EmoteSource::Unicode(_) => 72
+    };
+    let seed = emote.cache_key().bytes().fold(7u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+    let color = Rgba([(seed >> 16) as u8, (seed >> 8) as u8, seed as u8, 255]);
+    cache.insert_image(&emote, DynamicImage::ImageRgba8(RgbaImage::from_pixel(size, size, color)));
+  }
+}
+
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_compare_tolerates_noise() {
+    let base = RgbaImage::from_pixel(10, 10, Rgba([120, 120, 120, 255]));
+    let mut noisy = base.clone();
+    noisy.put_pixel(3, 3, Rgba([123, 121, 120, 255]));
+    assert_eq!(compare(&base, &noisy).1, 0);
+
+    noisy.put_pixel(4, 4, Rgba([255, 0, 0, 255]));
+    let (diff, mismatched) = compare(&base, &noisy);
+    assert_eq!(mismatched, 1);
+    assert_eq!(*diff.get_pixel(4, 4), Rgba([255, 0, 0, 255]));
+  }
+}
@@ -0,0 +1,423 @@
+use {
+  crate::{
+    canvas::Canvas,
+    fill::{
+      ColorStop,
+      Fill,
+      ImageFit
+    },
+    layer::{
+      Font,
+      Layer
+    },
+    raster::{
+      BlendMode,
+      Region
+    },
+    scene::image_data::to_data_uri
+  },
+  ab_glyph::{
+    Font as _,
+    PxScale,
+    ScaleFont
+  },
+  image::{
+    DynamicImage,
+    Rgba
+  },
+  std::fmt::Write
+};
+
+impl Canvas {
+  /// Exports the canvas as an SVG document<br>
+  /// Shapes, lines and text stay vector, images are embedded as base64 PNGs with their transforms already applied
+  pub fn to_svg(&self) -> String {
+    let mut svg = SvgWriter::default();
+    let background = svg.paint("fill", &self.background, Region::new(0, 0, self.width, self.height));
+    let mut body = format!(r#"<rect width="{}" height="{}" {background}/>"#, self.width, self.height);
+
+    for layer in &self.layers {
+      svg.layer(layer, &mut body);
+    }
+
+    format!(
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{ow}" height="{oh}" viewBox="0 0 {w} {h}"><defs>{defs}</defs>{body}</svg>"#,
+      ow = self.output_size().0,
+      oh = self.output_size().1,
+      w = self.width,
+      h = self.height,
+      defs = svg.defs
+    )
+  }
+}
+
+#[derive(Default)]
+struct SvgWriter {
+  defs:    String,
+  next_id: usize
+}
+
+impl SvgWriter {
+  fn id(
+    &mut self,
+    prefix: &str
+  ) -> String {
+    self.next_id += 1;
+    format!("{prefix}{}", self.next_id)
+  }
+
+  /// Paint attributes (`attr` being `fill` or `stroke`) for the element covering given area,
+  /// gradients and images go into the defs
+  fn paint(
+    &mut self,
+    attr: &str,
+    fill: &Fill,
+    area: Region
+  ) -> String {
+    match fill {
+      Fill::Solid(color) => color_attrs(attr, *color),
+      Fill::LinearGradient { start, end, stops } => {
+        let id = self.id("gradient");
+        let _ = write!(
+          self.defs,
+          r#"<linearGradient id="{id}" x1="{}" y1="{}" x2="{}" y2="{}">{}</linearGradient>"#,
+          start.0,
+          start.1,
+          end.0,
+          end.1,
+          stop_elements(stops)
+        );
+        format!(r#"{attr}="url(#{id})""#)
+      },
+      Fill::RadialGradient { center, radius, stops } => {
+        // the radius is relative to the longer side, so resolve it in user space
+        let id = self.id("gradient");
+        let cx = area.x as f32 + center.0 * area.width as f32;
+        let cy = area.y as f32 + center.1 * area.height as f32;
+        let r = radius * area.width.max(area.height) as f32;
+        let _ = write!(
+          self.defs,
+          r#"<radialGradient id="{id}" gradientUnits="userSpaceOnUse" cx="{cx}" cy="{cy}" r="{r}">{}</radialGradient>"#,
+          stop_elements(stops)
+        );
+        format!(r#"{attr}="url(#{id})""#)
+      },
+      Fill::Image { image, fit, blur } => {
+        let id = self.id("pattern");
+        let aspect = match fit {
+          ImageFit::Cover => "xMidYMid slice",
+          ImageFit::Contain => "xMidYMid meet",
+          ImageFit::Stretch => "none"
+        };
+        let filter = if *blur > 0.0 {
+          let filter_id = self.id("blur");
+          let _ = write!(self.defs, r#"<filter id="{filter_id}"><feGaussianBlur stdDeviation="{blur}"/></filter>"#);
+          format!(r#" filter="url(#{filter_id})""#)
+        } else {
+          String::new()
+        };
+        let _ = write!(
+          self.defs,
+          r#"<pattern id="{id}" patternUnits="userSpaceOnUse" x="{x}" y="{y}" width="{w}" height="{h}"><image x="0" y="0" width="{w}" height="{h}" preserveAspectRatio="{aspect}" href="{href}"{filter}/></pattern>"#,
+          x = area.x,
+          y = area.y,
+          w = area.width,
+          h = area.height,
+          href = data_uri(image)
+        );
+        format!(r#"{attr}="url(#{id})""#)
+      }
+    }
+  }
+
+  /// Fill attributes for the shape, or stroke attributes with given width when it only has a border
+  fn shape_paint(
+    &mut self,
+    fill: &Fill,
+    area: Region,
+    border: Option<u32>
+  ) -> String {
+    match border {
+      Some(width) => format!(r#"fill="none" stroke-width="{width}" {}"#, self.paint("stroke", fill, area)),
+      None => self.paint("fill", fill, area)
+    }
+  }
+
+  fn layer(
+    &mut self,
+    layer: &Layer,
+    out: &mut String
+  ) {
+    match layer {
+      Layer::Rect { size, position, fill } => {
+        let paint = self.paint("fill", fill, Region::new(position.0, position.1, size.0, size.1));
+        let _ = write!(
+          out,
+          r#"<rect x="{}" y="{}" width="{}" height="{}" {paint}/>"#,
+          position.0, position.1, size.0, size.1
+        );
+      },
+      Layer::RoundedRect {
+        size,
+        position,
+        radius,
+        fill,
+        border
+      } => {
+        let area = Region::new(position.0, position.1, size.0, size.1);
+        let paint = self.shape_paint(fill, area, *border);
+        // strokes are centered on the path, inset it so the border stays inside like when rasterized
+        let inset = border.map_or(0.0, |w| w as f32 / 2.0);
+        let _ = write!(
+          out,
+          r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}/>"#,
+          position.0 as f32 + inset,
+          position.1 as f32 + inset,
+          (size.0 as f32 - inset * 2.0).max(0.0),
+          (size.1 as f32 - inset * 2.0).max(0.0),
+          (*radius as f32 - inset).max(0.0),
+          paint
+        );
+      },
+      Layer::Circle {
+        center,
+        radius,
+        fill,
+        border
+      } => {
+        let paint = self.shape_paint(fill, layer.bounds().unwrap_or(Region::new(0, 0, 0, 0)), *border);
+        let inset = border.map_or(0.0, |w| w as f32 / 2.0);
+        let _ = write!(
+          out,
+          r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+          center.0,
+          center.1,
+          (*radius as f32 - inset).max(0.0),
+          paint
+        );
+      },
+      Layer::Ellipse { center, radii, fill, border } => {
+        let paint = self.shape_paint(fill, layer.bounds().unwrap_or(Region::new(0, 0, 0, 0)), *border);
+        let inset = border.map_or(0.0, |w| w as f32 / 2.0);
+        let _ = write!(
+          out,
+          r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#,
+          center.0,
+          center.1,
+          (radii.0 as f32 - inset).max(0.0),
+          (radii.1 as f32 - inset).max(0.0),
+          paint
+        );
+      },
+      Layer::Polygon { points, fill, border } => {
+        let Some(area) = layer.bounds() else { return };
+        let paint = self.shape_paint(fill, area, *border);
+        let points = points.iter().map(|p| format!("{},{}", p.0, p.1)).collect::<Vec<_>>().join(" ");
+        let _ = write!(out, r#"<polygon points="{points}" fill-rule="evenodd" {}/>"#, paint);
+      },
+      Layer::Line { start, end, width, color } => {
+        let _ = write!(
+          out,
+          r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke-linecap="round" {}/>"#,
+          start.0 as f32 + 0.5,
+          start.1 as f32 + 0.5,
+          end.0 as f32 + 0.5,
+          end.1 as f32 + 0.5,
+          (*width).max(1),
+          color_attrs("stroke", *color)
+        );
+      },
+      Layer::Text {
+        size,
+        position,
+        color,
+        content,
+        font
+      } => {
+        // rasterized text is positioned by its top edge, SVG uses the baseline
+        let font_ref = font.to_fontarc();
+        let ascent = font_ref.as_scaled(PxScale::from(*size)).ascent();
+        // the scale is the height from ascent to descent, SVG sizes text by its em square
+        let em_size = size * font_ref.units_per_em().unwrap_or(font_ref.height_unscaled()) / font_ref.height_unscaled().max(1.0);
+        let (family, weight) = font_family(*font);
+        let _ = write!(
+          out,
+          r#"<text x="{}" y="{}" font-family="{}" font-weight="{weight}" font-size="{em_size}" xml:space="preserve" {}>{}</text>"#,
+          position.0,
+          position.1 as f32 + ascent,
+          escape(&family),
+          color_attrs("fill", *color),
+          escape(content)
+        );
+      },
+      Layer::Image { scale, image, transform, .. } => {
+        let Some(area) = layer.bounds() else { return };
+        let baked = DynamicImage::ImageRgba8(transform.apply(image, *scale));
+        let _ = write!(
+          out,
+          r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"/>"#,
+          area.x,
+          area.y,
+          baked.width(),
+          baked.height(),
+          data_uri(&baked)
+        );
+      },
+      Layer::Group {
+        layers,
+        clip,
+        opacity,
+        blend,
+        ..
+      } => {
+        let Some(shift) = layer.group_shift() else { return };
+        let _ = write!(out, r#"<g transform="translate({},{})""#, shift.0, shift.1);
+
+        if let Some(clip) = clip {
+          let id = self.id("clip");
+          let _ = write!(
+            self.defs,
+            r#"<clipPath id="{id}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+            clip.x, clip.y, clip.width, clip.height
+          );
+          let _ = write!(out, r#" clip-path="url(#{id})""#);
+        }
+        if *opacity < 1.0 {
+          let _ = write!(out, r#" opacity="{opacity}""#);
+        }
+        if *blend != BlendMode::Normal {
+          let mode = match blend {
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::Normal => "normal"
+          };
+          let _ = write!(out, r#" style="mix-blend-mode:{mode};isolation:isolate""#);
+        }
+        out.push('>');
+
+        for layer in layers {
+          self.layer(layer, out);
+        }
+        out.push_str("</g>");
+      }
+    }
+  }
+}
+
+fn color_attrs(
+  attr: &str,
+  color: Rgba<u8>
+) -> String {
+  let [r, g, b, a] = color.0;
+  if a == 255 {
+    format!(r##"{attr}="#{r:02X}{g:02X}{b:02X}""##)
+  } else {
+    format!(r##"{attr}="#{r:02X}{g:02X}{b:02X}" {attr}-opacity="{:.3}""##, a as f32 / 255.0)
+  }
+}
+
+fn stop_elements(stops: &[ColorStop]) -> String {
+  stops.iter().fold(String::new(), |mut out, stop| {
+    let [r, g, b, a] = stop.color.0;
+    let _ = write!(
+      out,
+      r##"<stop offset="{}" stop-color="#{r:02X}{g:02X}{b:02X}" stop-opacity="{:.3}"/>"##,
+      stop.offset,
+      a as f32 / 255.0
+    );
+    out
+  })
+}
+
+fn font_family(font: Font) -> (String, u32) {
+  match font {
+    Font::DejaVuSans => ("DejaVu Sans".into(), 400),
+    Font::UbuntuRegular => ("Ubuntu".into(), 400),
+    Font::UbuntuBold => ("Ubuntu".into(), 700),
+    Font::RobotoRegular => ("Roboto".into(), 400),
+    Font::RobotoBold => ("Roboto".into(), 700),
+    Font::Custom(path) => {
+      let stem = std::path::Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or(path);
+      (stem.to_string(), 400)
+    }
+  }
+}
+
+fn data_uri(image: &DynamicImage) -> String { to_data_uri(image).unwrap_or_default() }
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::templates::playerlist::{
+      PlayerEntry,
+      playerlist
+    }
+  };
+
+  #[test]
+  fn test_svg_elements() {
+    let mut canvas = Canvas::new(100, 50);
+    canvas.add_layer(Layer::RoundedRect {
+      size:     (80, 30),
+      position: (10, 10),
+      radius:   6,
+      fill:     Fill::horizontal(&[Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 128])]),
+      border:   None
+    });
+    canvas.add_layer(
+      Layer::Text {
+        size:     14.0,
+        position: (12, 14),
+        color:    Rgba([255, 255, 255, 255]),
+        content:  "<Asahi & co>".to_string(),
+        font:     Font::UbuntuBold
+      }
+      .with_opacity(0.5)
+    );
+    canvas.add_layer(Layer::Circle {
+      center: (50, 25),
+      radius: 20,
+      fill:   Rgba([0, 255, 0, 128]).into(),
+      border: Some(3)
+    });
+
+    let svg = canvas.to_svg();
+    assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+    assert!(svg.contains("<linearGradient"));
+    assert!(svg.contains(r#"rx="6""#));
+    assert!(svg.contains(r#"font-family="Ubuntu" font-weight="700""#));
+    assert!(svg.contains("&lt;Asahi &amp; co&gt;"));
+    assert!(svg.contains(r#"opacity="0.5""#));
+    assert!(svg.contains(r##"fill="none" stroke-width="3" stroke="#00FF00" stroke-opacity="0.502""##));
+
+    let font = Font::UbuntuBold.to_fontarc();
+    let em_size = 14.0 * font.units_per_em().unwrap() / font.height_unscaled();
+    assert!(em_size < 14.0);
+    assert!(svg.contains(&format!(r#"font-size="{em_size}""#)));
+  }
+
+  #[test]
+  fn test_playerlist_svg() {
+    let players = [PlayerEntry {
+      name: "Nwero".to_string(),
+      uptime: "4 h".to_string(),
+      is_admin: true,
+      emoji: String::new(),
+      ..Default::default()
+    }];
+    let svg = playerlist(&players, &[1, 3, 2], true, None).to_svg();
+
+    assert!(svg.contains(">Nwero</text>"));
+    assert!(svg.contains("<line"));
+  }
+}
@@ -1,5 +1,35 @@
+pub mod bar_chart;
+pub mod chart;
 pub mod explorer;
-mod graph;
+pub mod leaderboard;
+pub mod line_chart;
+pub mod pie_chart;
 pub mod playerlist;
+pub mod rank_card;
+pub mod server_status;
+pub mod sparkline;
+pub mod table;
 
-pub(crate) use playerlist::DISCORD_EMOTES_CACHE;
+use {
+  crate::layer::{
+    Font,
+    Layer
+  },
+  image::Rgba
+};
+
+/// Text layer at the origin, meant to be placed with [Layer::anchored]
+fn text(
+  content: &str,
+  size: f32,
+  color: Rgba<u8>,
+  font: Font
+) -> Layer {
+  Layer::Text {
+    size,
+    position: (0, 0),
+    color,
+    content: content.to_string(),
+    font
+  }
+}
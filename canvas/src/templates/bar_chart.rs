@@ -0,0 +1,319 @@
+use {
+  super::chart::{
+    ChartStyle,
+    ValueScale
+  },
+  crate::{
+    canvas::{
+      Canvas,
+      assume_text_width
+    },
+    layer::{
+      Anchor,
+      Layer
+    },
+    raster::Region
+  },
+  image::Rgba
+};
+
+/// Values of one kind, one per category
+#[derive(Debug, Clone)]
+pub struct Dataset {
+  /// Shown in the legend
+  pub name:   String,
+  pub values: Vec<f64>,
+  pub color:  Rgba<u8>
+}
+
+impl Dataset {
+  pub fn new(
+    name: impl Into<String>,
+    color: Rgba<u8>,
+    values: Vec<f64>
+  ) -> Self {
+    Self {
+      name: name.into(),
+      values,
+      color
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+  /// Bars grow upwards from the categories along the bottom
+  #[default]
+  Vertical,
+  /// Bars grow rightwards from the categories along the left side
+  Horizontal
+}
+
+/// How the bars are arranged
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BarLayout {
+  pub orientation: Orientation,
+  /// Piles the datasets into one bar per category instead of placing them side by side
+  pub stacked:     bool,
+  /// Writes the value on each bar
+  pub show_values: bool
+}
+
+/// Renders the datasets as bars grouped by category into a chart of given size
+pub fn bar_chart(
+  width: u32,
+  height: u32,
+  categories: &[&str],
+  datasets: &[Dataset],
+  layout: BarLayout,
+  style: Option<ChartStyle>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let mut canvas = Canvas::new(width.max(1), height.max(1));
+  canvas.set_bg_color(style.bg_color);
+  draw_bar_chart(&mut canvas, Region::new(0, 0, width, height), categories, datasets, layout, &style);
+  canvas
+}
+
+/// Draws the bar chart into the area of an existing canvas, the background is left as is
+pub fn draw_bar_chart(
+  canvas: &mut Canvas,
+  area: Region,
+  categories: &[&str],
+  datasets: &[Dataset],
+  layout: BarLayout,
+  style: &ChartStyle
+) {
+  let font = style.font.to_fontarc();
+  let pad = style.padding as i32;
+  let legend: Vec<_> = datasets.iter().map(|d| (d.name.as_str(), d.color)).collect();
+  let top = style.draw_header(canvas, area.x + pad, area.y + pad, &legend);
+
+  let count = datasets.iter().map(|d| d.values.len()).max().unwrap_or(0).max(categories.len());
+  let value = |d: &Dataset, i: usize| d.values.get(i).copied().filter(|v| v.is_finite()).unwrap_or(0.0);
+  let scale = if layout.stacked {
+    let sums = (0..count).flat_map(|i| {
+      let (pos, neg) = datasets
+        .iter()
+        .map(|d| value(d, i))
+        .fold((0.0, 0.0), |(p, n), v| if v >= 0.0 { (p + v, n) } else { (p, n + v) });
+      [pos, neg]
+    });
+    ValueScale::new(sums, style)
+  } else {
+    ValueScale::new(datasets.iter().flat_map(|d| d.values.iter().copied()), style)
+  };
+
+  let vertical = layout.orientation == Orientation::Vertical;
+  let value_labels: Vec<String> = scale.ticks().iter().map(|v| scale.label(*v)).collect();
+  let widest = |labels: &mut dyn Iterator<Item = &str>| labels.map(|l| assume_text_width(l, style.font_size, &font)).max().unwrap_or(0) as i32;
+  let (left, bottom) = match (style.show_axes, vertical) {
+    (false, _) => (0, 0),
+    (true, true) => (widest(&mut value_labels.iter().map(String::as_str)) + pad / 2, style.line_height()),
+    (true, false) => (widest(&mut categories.iter().copied()) + pad / 2, style.line_height())
+  };
+  let plot = Region::new(
+    area.x + pad + left,
+    top,
+    (area.right() - pad - (area.x + pad + left)).max(0) as u32,
+    (area.bottom() - pad - bottom - top).max(0) as u32
+  );
+  if plot.width < 2 || plot.height < 2 {
+    return
+  }
+
+  // distances along the value axis and the category axis
+  let (value_len, category_len) = if vertical {
+    (plot.height, plot.width)
+  } else {
+    (plot.width, plot.height)
+  };
+  let to_value_px = |v: f64| (scale.fraction(v) * value_len as f64).round() as i32;
+  let place = |from: i32, to: i32, at: i32, size: i32| -> ((i32, i32), (u32, u32)) {
+    let (lo, hi) = (from.min(to), from.max(to));
+    if vertical {
+      ((at, plot.bottom() - hi), (size.max(1) as u32, (hi - lo) as u32))
+    } else {
+      ((plot.x + lo, at), ((hi - lo) as u32, size.max(1) as u32))
+    }
+  };
+
+  let last_tick = value_labels.len().saturating_sub(1);
+  for (i, (tick, label)) in scale.ticks().into_iter().zip(&value_labels).enumerate() {
+    let at = to_value_px(tick);
+    let (start, end, label_at, anchor) = if vertical {
+      let y = plot.bottom() - at;
+      ((plot.x, y), (plot.right(), y), (plot.x - pad / 2, y), Anchor::Right)
+    } else {
+      let x = plot.x + at;
+      let anchor = if i == last_tick { Anchor::TopRight } else { Anchor::Top };
+      ((x, plot.y), (x, plot.bottom()), (x, plot.bottom() + 4), anchor)
+    };
+    if style.show_grid {
+      canvas.add_layer(Layer::Line {
+        start,
+        end,
+        width: 1,
+        color: style.grid_color
+      });
+    }
+    if style.show_axes {
+      canvas.add_layer(style.text(label, (0, 0)).anchored(anchor, label_at));
+    }
+  }
+
+  if count == 0 {
+    return
+  }
+  let band = category_len as f32 / count as f32;
+  let group = band * 0.7;
+  let slots = if layout.stacked { 1 } else { datasets.len().max(1) };
+  let bar = (group / slots as f32 - if slots > 1 { 2.0 } else { 0.0 }).max(1.0);
+  let zero = to_value_px(0.0_f64.clamp(scale.min, scale.max));
+
+  for i in 0..count {
+    let band_start = if vertical { plot.x } else { plot.y } as f32 + band * i as f32;
+    let group_start = band_start + (band - group) / 2.0;
+    let (mut pos, mut neg) = (0.0, 0.0);
+
+    for (j, d) in datasets.iter().enumerate() {
+      let v = value(d, i);
+      let (from, to) = if layout.stacked {
+        let base = if v >= 0.0 { &mut pos } else { &mut neg };
+        let from = *base;
+        *base += v;
+        (to_value_px(from), to_value_px(*base))
+      } else {
+        (zero, to_value_px(v))
+      };
+      if from == to {
+        continue
+      }
+
+      let at = group_start + if layout.stacked { 0.0 } else { j as f32 * (bar + 2.0) };
+      let (position, size) = place(from, to, at.round() as i32, bar.round() as i32);
+      canvas.add_layer(Layer::Rect {
+        size,
+        position,
+        fill: d.color.into()
+      });
+
+      if layout.show_values {
+        let label = scale.label(v);
+        let center = (position.0 + size.0 as i32 / 2, position.1 + size.1 as i32 / 2);
+        let layer = if layout.stacked {
+          // written inside the segment, only when there's room for it
+          let (along, across) = if vertical { (size.1, size.0) } else { (size.0, size.1) };
+          let text_width = assume_text_width(&label, style.font_size, &font);
+          let fits = if vertical {
+            along as i32 > style.line_height() && across >= text_width
+          } else {
+            along >= text_width + 4 && across as i32 >= style.line_height()
+          };
+          if !fits {
+            continue
+          }
+          Layer::Text {
+            size:     style.font_size,
+            position: (0, 0),
+            color:    style.label_color,
+            content:  label,
+            font:     style.font
+          }
+          .anchored(Anchor::Center, center)
+        } else if vertical {
+          let (anchor, y) = if v >= 0.0 {
+            (Anchor::Bottom, position.1 - 2)
+          } else {
+            (Anchor::Top, position.1 + size.1 as i32 + 2)
+          };
+          style.text(&label, (0, 0)).anchored(anchor, (center.0, y))
+        } else {
+          let (anchor, x) = if v >= 0.0 {
+            (Anchor::Left, position.0 + size.0 as i32 + 4)
+          } else {
+            (Anchor::Right, position.0 - 4)
+          };
+          style.text(&label, (0, 0)).anchored(anchor, (x, center.1))
+        };
+        canvas.add_layer(layer);
+      }
+    }
+
+    if style.show_axes
+      && let Some(name) = categories.get(i)
+    {
+      let middle = (band_start + band / 2.0).round() as i32;
+      let layer = if vertical {
+        style.text(name, (0, 0)).anchored(Anchor::Top, (middle, plot.bottom() + 4))
+      } else {
+        style.text(name, (0, 0)).anchored(Anchor::Right, (plot.x - pad / 2, middle))
+      };
+      canvas.add_layer(layer);
+    }
+  }
+
+  if style.show_axes {
+    let (start, end) = if vertical {
+      ((plot.x, plot.bottom() - zero), (plot.right(), plot.bottom() - zero))
+    } else {
+      ((plot.x + zero, plot.y), (plot.x + zero, plot.bottom()))
+    };
+    canvas.add_layer(Layer::Line {
+      start,
+      end,
+      width: 1,
+      color: style.axis_color
+    });
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot
+  };
+
+  fn week() -> [Dataset; 2] {
+    [
+      Dataset::new("Messages", Rgba([88, 101, 242, 255]), vec![120.0, 95.0, 140.0, 80.0, 160.0, 210.0, 180.0]),
+      Dataset::new("Commands", Rgba([235, 69, 158, 255]), vec![40.0, 35.0, 60.0, 20.0, 55.0, 90.0, 70.0])
+    ]
+  }
+
+  const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+  #[test]
+  fn test_snapshot() {
+    let layout = BarLayout {
+      show_values: true,
+      ..Default::default()
+    };
+    let style = ChartStyle {
+      title: Some("Weekly activity".to_string()),
+      ..Default::default()
+    };
+    assert_snapshot("bar_chart", &bar_chart(640, 320, &DAYS, &week(), layout, Some(style)));
+  }
+
+  #[test]
+  fn test_snapshot_stacked_horizontal() {
+    let layout = BarLayout {
+      orientation: Orientation::Horizontal,
+      stacked:     true,
+      show_values: true
+    };
+    assert_snapshot(
+      "bar_chart_stacked",
+      &bar_chart(480, 360, &DAYS, &week(), layout, Some(ChartStyle::light()))
+    );
+  }
+
+  #[test]
+  fn test_negative_and_empty() {
+    let data = [Dataset::new("Delta", Rgba([255, 0, 0, 255]), vec![-3.0, 5.0, f64::NAN])];
+    assert_eq!(bar_chart(200, 120, &[], &data, BarLayout::default(), None).width, 200);
+    assert_eq!(bar_chart(0, 0, &DAYS, &[], BarLayout::default(), None).width, 1);
+  }
+}
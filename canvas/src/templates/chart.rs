@@ -0,0 +1,323 @@
+use {
+  crate::{
+    canvas::{
+      Canvas,
+      assume_text_width
+    },
+    layer::{
+      Font as LFont,
+      Layer
+    }
+  },
+  image::Rgba
+};
+
+/// How the X values of a [line_chart](super::line_chart::line_chart) are labelled
+#[derive(Debug, Clone)]
+pub enum XAxis {
+  Values,
+  /// X values are unix timestamps formatted with the pattern of
+  /// [format_unix_time](asahi_utils::format_unix_time), e.g `"%H:%M"`
+  Time(String)
+}
+
+/// Style override options shared by every chart template
+#[derive(Clone)]
+pub struct ChartStyle {
+  /// Drawn above the chart if set<br>
+  /// Defaults to `None`
+  pub title:           Option<String>,
+  pub bg_color:        Rgba<u8>,
+  pub text_color:      Rgba<u8>,
+  /// Colour of the values written over bars and slices
+  pub label_color:     Rgba<u8>,
+  pub grid_color:      Rgba<u8>,
+  pub axis_color:      Rgba<u8>,
+  pub font:            LFont,
+  pub font_size:       f32,
+  pub title_font_size: f32,
+  pub line_width:      u32,
+  /// Opacity of shaded areas under lines, from `0.0` to `1.0`
+  pub fill_opacity:    f32,
+  pub x_axis:          XAxis,
+  /// Labelled ticks along each axis, gridlines are drawn at the value ones<br>
+  /// Defaults to `5` and `5`
+  pub ticks:           (usize, usize),
+  /// Fixed bounds of the value axis, the data decides when `None` or not finite<br>
+  /// Defaults to `None` and `None`
+  pub y_range:         (Option<f64>, Option<f64>),
+  /// Keeps 0 on the value axis when auto-scaling<br>
+  /// Defaults to `true`
+  pub include_zero:    bool,
+  pub show_grid:       bool,
+  /// Draws the axis lines and their labels
+  pub show_axes:       bool,
+  pub show_legend:     bool,
+  pub padding:         u32
+}
+
+impl Default for ChartStyle {
+  fn default() -> Self {
+    Self {
+      title:           None,
+      bg_color:        Rgba([30, 31, 34, 255]),
+      text_color:      Rgba([219, 222, 225, 255]),
+      label_color:     Rgba([255, 255, 255, 255]),
+      grid_color:      Rgba([255, 255, 255, 24]),
+      axis_color:      Rgba([255, 255, 255, 90]),
+      font:            LFont::UbuntuRegular,
+      font_size:       14.0,
+      title_font_size: 20.0,
+      line_width:      3,
+      fill_opacity:    0.25,
+      x_axis:          XAxis::Values,
+      ticks:           (5, 5),
+      y_range:         (None, None),
+      include_zero:    true,
+      show_grid:       true,
+      show_axes:       true,
+      show_legend:     true,
+      padding:         12
+    }
+  }
+}
+
+impl ChartStyle {
+  pub fn dark() -> Self { Self::default() }
+
+  pub fn light() -> Self {
+    Self {
+      bg_color: Rgba([255, 255, 255, 255]),
+      text_color: Rgba([46, 51, 56, 255]),
+      grid_color: Rgba([0, 0, 0, 20]),
+      axis_color: Rgba([0, 0, 0, 90]),
+      ..Default::default()
+    }
+  }
+
+  pub(super) fn text(
+    &self,
+    content: &str,
+    position: (i32, i32)
+  ) -> Layer {
+    Layer::Text {
+      size: self.font_size,
+      position,
+      color: self.text_color,
+      content: content.to_string(),
+      font: self.font
+    }
+  }
+
+  /// Draws the title and a legend entry for each name and colour from `top`, returns where the chart goes next
+  pub(super) fn draw_header(
+    &self,
+    canvas: &mut Canvas,
+    x: i32,
+    mut top: i32,
+    legend: &[(&str, Rgba<u8>)]
+  ) -> i32 {
+    let pad = self.padding as i32;
+
+    if let Some(title) = &self.title {
+      canvas.add_layer(Layer::Text {
+        size:     self.title_font_size,
+        position: (x, top),
+        color:    self.text_color,
+        content:  title.clone(),
+        font:     self.font
+      });
+      top += self.title_font_size.ceil() as i32 + pad / 2;
+    }
+
+    if self.show_legend && !legend.is_empty() {
+      let font = self.font.to_fontarc();
+      let swatch = (self.font_size.round() as u32).saturating_sub(2);
+      let mut x = x;
+      for (name, color) in legend {
+        canvas.add_layer(Layer::RoundedRect {
+          size:     (swatch, swatch),
+          position: (x, top + 3),
+          radius:   3,
+          fill:     (*color).into(),
+          border:   None
+        });
+        x += swatch as i32 + 6;
+        canvas.add_layer(self.text(name, (x, top)));
+        x += assume_text_width(name, self.font_size, &font) as i32 + pad;
+      }
+      top += self.line_height() + pad / 2;
+    }
+
+    top
+  }
+
+  pub(super) fn line_height(&self) -> i32 { self.font_size.ceil() as i32 + 4 }
+
+  /// Colour with its alpha scaled by [ChartStyle::fill_opacity]
+  pub(super) fn faded(
+    &self,
+    mut color: Rgba<u8>
+  ) -> Rgba<u8> {
+    color.0[3] = (self.fill_opacity.clamp(0.0, 1.0) * color.0[3] as f32) as u8;
+    color
+  }
+}
+
+/// Most ticks drawn on a value axis, however wide its range
+const MAX_TICKS: usize = 64;
+
+/// Largest magnitude a value axis bound can have, so the distance between the bounds stays finite
+const MAX_BOUND: f64 = f64::MAX / 4.0;
+
+/// Value axis bounds rounded out to a readable step
+pub(super) struct ValueScale {
+  pub min:  f64,
+  pub max:  f64,
+  pub step: f64
+}
+
+impl ValueScale {
+  /// Fits the values, honouring [ChartStyle::y_range] and [ChartStyle::include_zero]<br>
+  /// Bounds are clamped to [MAX_BOUND] so the range stays finite
+  pub(super) fn new(
+    values: impl Iterator<Item = f64>,
+    style: &ChartStyle
+  ) -> Self {
+    let (lo, hi) = values
+      .filter(|v| v.is_finite())
+      .fold((f64::MAX, f64::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+    let (mut lo, mut hi) = if lo > hi { (0.0, 1.0) } else { (lo, hi) };
+    if style.include_zero {
+      (lo, hi) = (lo.min(0.0), hi.max(0.0));
+    }
+    let bound = |bound: Option<f64>| bound.filter(|v| v.is_finite()).map(|v| v.clamp(-MAX_BOUND, MAX_BOUND));
+    let (min, max) = (bound(style.y_range.0), bound(style.y_range.1));
+    let (lo, hi) = widen(
+      min.unwrap_or(lo.clamp(-MAX_BOUND, MAX_BOUND)),
+      max.unwrap_or(hi.clamp(-MAX_BOUND, MAX_BOUND))
+    );
+
+    let step = nice_step((hi - lo) / style.ticks.1.max(1) as f64);
+    Self {
+      min: min.unwrap_or((lo / step).floor() * step),
+      max: max.unwrap_or((hi / step).ceil() * step),
+      step
+    }
+  }
+
+  /// Values to put a tick at, never more than [MAX_TICKS] plus the minimum
+  pub(super) fn ticks(&self) -> Vec<f64> {
+    let count = (((self.max - self.min) / self.step).round() as usize).min(MAX_TICKS);
+    (0..=count).map(|i| self.min + self.step * i as f64).collect()
+  }
+
+  pub(super) fn label(
+    &self,
+    value: f64
+  ) -> String {
+    format_value(value, self.step)
+  }
+
+  /// Position of the value between the bounds, `0.0` at the minimum and `1.0` at the maximum
+  pub(super) fn fraction(
+    &self,
+    value: f64
+  ) -> f64 {
+    (value - self.min) / (self.max - self.min)
+  }
+}
+
+/// Gives an empty range some room so it can be divided, more than `0.5` on each side for values too large to notice it
+pub(super) fn widen(
+  lo: f64,
+  hi: f64
+) -> (f64, f64) {
+  let pad = 0.5f64.max(lo.abs() * f64::EPSILON);
+  if hi > lo { (lo, hi) } else { (lo - pad, lo + pad) }
+}
+
+/// Rounds the step up to 1, 2 or 5 times a power of ten
+fn nice_step(raw: f64) -> f64 {
+  if !raw.is_normal() || raw < 0.0 {
+    return 1.0
+  }
+  let magnitude = 10f64.powf(raw.log10().floor());
+  let fraction = raw / magnitude;
+  let nice = if fraction <= 1.0 {
+    1.0
+  } else if fraction <= 2.0 {
+    2.0
+  } else if fraction <= 5.0 {
+    5.0
+  } else {
+    10.0
+  };
+  nice * magnitude
+}
+
+/// Formats the value with just enough decimals to tell ticks `step` apart
+pub(super) fn format_value(
+  value: f64,
+  step: f64
+) -> String {
+  let decimals = if step >= 1.0 || step <= 0.0 {
+    0
+  } else {
+    (-step.log10()).ceil() as usize
+  };
+  format!("{:.*}", decimals, value + 0.0)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_value_scale() {
+    let scale = ValueScale::new([3.0, 47.0].into_iter(), &ChartStyle::default());
+    assert_eq!((scale.min, scale.max, scale.step), (0.0, 50.0, 10.0));
+    assert_eq!(scale.label(20.0), "20");
+
+    let scale = ValueScale::new(
+      [0.12, 0.31].into_iter(),
+      &ChartStyle {
+        include_zero: false,
+        ..Default::default()
+      }
+    );
+    assert_eq!((scale.label(scale.min), scale.step), ("0.10".to_string(), 0.05));
+  }
+
+  #[test]
+  fn test_value_scale_extremes() {
+    let scale = ValueScale::new([f64::MAX, -f64::MAX].into_iter(), &ChartStyle::default());
+    assert!(scale.min.is_finite() && scale.max.is_finite() && (scale.max - scale.min).is_finite());
+    assert!(scale.ticks().len() <= MAX_TICKS + 1);
+
+    let scale = ValueScale::new(
+      [3.0, 47.0].into_iter(),
+      &ChartStyle {
+        y_range: (Some(f64::NEG_INFINITY), Some(f64::NAN)),
+        ..Default::default()
+      }
+    );
+    assert_eq!((scale.min, scale.max, scale.step), (0.0, 50.0, 10.0));
+
+    let scale = ValueScale::new(
+      [1e300, 1e300].into_iter(),
+      &ChartStyle {
+        include_zero: false,
+        ..Default::default()
+      }
+    );
+    assert!(scale.max > scale.min);
+
+    let tight = ValueScale {
+      min:  0.0,
+      max:  1e12,
+      step: 1e-3
+    };
+    assert_eq!(tight.ticks().len(), MAX_TICKS + 1);
+  }
+}
@@ -1,38 +1,105 @@
 use {
+  super::table::{
+    Align,
+    Column,
+    ColumnWidth,
+    Style as TableStyle,
+    draw_table
+  },
   crate::{
+    Region,
     canvas::Canvas,
     layer::{
+      Anchor,
       Font as LFont,
       Layer
     },
     to_rgba
   },
-  asahi_utils::format_bytes,
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
+  },
+  asahi_utils::{
+    format_bytes,
+    format_unix_time
+  },
   image::Rgba,
-  std::fmt
+  std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    fs,
+    path::Path,
+    sync::{
+      LazyLock,
+      RwLock
+    },
+    time::UNIX_EPOCH
+  }
 };
 
+/// File types added with [register_file_type], keyed by lowercase extension or MIME type
+static CUSTOM_FILE_TYPES: LazyLock<RwLock<HashMap<String, FileIcon>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
 #[derive(Debug, Clone)]
 pub struct Metadata {
-  pub name:      String,
-  pub extension: String,
-  pub is_folder: bool,
+  pub name:       String,
+  pub extension:  String,
+  pub is_folder:  bool,
   /// File size in bytes, it will be hidden if `is_folder` is true
-  pub size:      Option<u64>,
+  pub size:       Option<u64>,
   /// Displayed in 'Date modified' column
-  pub date:      String,
-  pub icon:      FileIcon
+  pub date:       String,
+  /// Unix seconds of the last change, [SortBy::Date] compares the `date` text without it
+  pub modified:   Option<i64>,
+  pub icon:       FileIcon,
+  /// Gets a shortcut arrow over its icon
+  pub is_symlink: bool,
+  /// Dotfiles and files with the hidden attribute, their icon is faded
+  pub is_hidden:  bool
+}
+
+/// How [Metadata::from_path] and [read_dir] read the filesystem
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+  /// Pattern of the 'Date modified' column, see [format_unix_time]<br>
+  /// Defaults to `"%d/%m/%Y %H:%M"`
+  pub date_pattern:    String,
+  /// Describes what a symlink points to instead of the link itself, broken links stay as they are<br>
+  /// Defaults to `true`
+  pub follow_symlinks: bool,
+  /// Lists hidden files in [read_dir]<br>
+  /// Defaults to `false`
+  pub show_hidden:     bool
+}
+
+impl Default for ReadOptions {
+  fn default() -> Self {
+    Self {
+      date_pattern:    String::from("%d/%m/%Y %H:%M"),
+      follow_symlinks: true,
+      show_hidden:     false
+    }
+  }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileIcon {
   Folder,
   Document,
+  Pdf,
+  Spreadsheet,
+  Presentation,
+  /// Config and structured data like JSON, TOML or YAML
+  Data,
   Executable,
   Archive,
+  DiskImage,
   Image,
   Video,
   Audio,
+  Font,
   Code,
   Unknown
 }
@@ -48,7 +115,10 @@ impl Metadata {
       is_folder: true,
       size: None,
       date,
-      icon: FileIcon::Folder
+      modified: None,
+      icon: FileIcon::Folder,
+      is_symlink: false,
+      is_hidden: false
     }
   }
 
@@ -58,44 +128,355 @@ impl Metadata {
     extension: String,
     size: u64
   ) -> Self {
-    let icon = FileIcon::get_icon(&extension);
+    let icon = FileIcon::from_extension(&extension);
     Self {
       name,
       extension,
       is_folder: false,
       size: Some(size),
       date,
-      icon
+      modified: None,
+      icon,
+      is_symlink: false,
+      is_hidden: false
+    }
+  }
+
+  /// Reads the file or folder at the path, the name and extension come from its last component
+  pub fn from_path(
+    path: impl AsRef<Path>,
+    options: &ReadOptions
+  ) -> AsahiResult<Self> {
+    let path = path.as_ref();
+    let io_error = |e: std::io::Error| AsahiError::External(format!("failed to read {}: {e}", path.display()).into());
+    let link = fs::symlink_metadata(path).map_err(io_error)?;
+    let is_symlink = link.file_type().is_symlink();
+    let meta = if is_symlink && options.follow_symlinks {
+      fs::metadata(path).unwrap_or_else(|_| link.clone())
+    } else {
+      link.clone()
+    };
+
+    let file_name = path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_else(|| path.display().to_string());
+    let is_hidden = file_name.starts_with('.') || has_hidden_attribute(&link);
+    let modified = meta
+      .modified()
+      .ok()
+      .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+      .map(|since| since.as_secs() as i64);
+    let date = modified.map(|secs| format_unix_time(secs, &options.date_pattern)).unwrap_or_default();
+
+    let mut metadata = if meta.is_dir() {
+      Self::new_folder(file_name, date)
+    } else {
+      let (name, extension) = split_extension(&file_name);
+      Self::new_file(name, date, extension, meta.len())
+    };
+    metadata.modified = modified;
+    metadata.is_symlink = is_symlink;
+    metadata.is_hidden = is_hidden;
+    Ok(metadata)
+  }
+
+  /// Name with the extension as shown in the list
+  pub fn file_name(&self) -> String {
+    if self.extension.is_empty() || self.is_folder {
+      self.name.clone()
+    } else {
+      format!("{}.{}", self.name, self.extension)
+    }
+  }
+}
+
+/// Reads every entry of the folder ordered by name, hidden ones only with [ReadOptions::show_hidden]<br>
+/// Entries that can't be read, e.g. removed while listing, are left out, only failing to open the folder is an error
+pub fn read_dir(
+  path: impl AsRef<Path>,
+  options: &ReadOptions
+) -> AsahiResult<Vec<Metadata>> {
+  let path = path.as_ref();
+  let entries = fs::read_dir(path).map_err(|e| AsahiError::External(format!("failed to list {}: {e}", path.display()).into()))?;
+
+  let mut files = Vec::new();
+  for entry in entries.flatten() {
+    let Ok(metadata) = Metadata::from_path(entry.path(), options) else {
+      continue
+    };
+    if options.show_hidden || !metadata.is_hidden {
+      files.push(metadata);
     }
   }
+  files.sort_by_key(|f| f.file_name().to_lowercase());
+  Ok(files)
+}
+
+/// Splits the extension off a file name, keeping `tar.gz` like ones whole and leaving dotfiles like `.env` without one
+fn split_extension(file_name: &str) -> (String, String) {
+  match file_name.rsplit_once('.') {
+    Some((stem, extension)) if !stem.is_empty() => match stem.rsplit_once('.') {
+      Some((inner, "tar")) if !inner.is_empty() => (inner.to_string(), format!("tar.{extension}")),
+      _ => (stem.to_string(), extension.to_string())
+    },
+    _ => (file_name.to_string(), String::new())
+  }
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(meta: &fs::Metadata) -> bool {
+  use std::os::windows::fs::MetadataExt;
+  const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+  meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_: &fs::Metadata) -> bool { false }
+
+/// Maps an extension like `"blend"` or a MIME type like `"application/x-blender"` to an icon,
+/// taking priority over the built-in mapping of [FileIcon::from_extension] and [FileIcon::from_mime]
+pub fn register_file_type(
+  key: &str,
+  icon: FileIcon
+) {
+  CUSTOM_FILE_TYPES
+    .write()
+    .expect("failed to acquire lock")
+    .insert(key.trim_start_matches('.').to_ascii_lowercase(), icon);
 }
 
+fn custom_file_type(key: &str) -> Option<FileIcon> { CUSTOM_FILE_TYPES.read().expect("failed to acquire lock").get(key).copied() }
+
 impl FileIcon {
-  fn get_icon(extension: &str) -> Self {
-    match extension.to_ascii_lowercase().as_str() {
-      "jpg" | "jpeg" | "png" | "webp" | "gif" => Self::Image,
-      "mp4" | "webm" => Self::Video,
-      "mp3" | "wav" | "ogg" => Self::Audio,
-      "exe" | "msi" => Self::Executable,
-      "zip" | "rar" | "7z" => Self::Archive,
-      "txt" | "log" => Self::Document,
-      "xml" | "js" | "lua" | "rs" => Self::Code,
+  /// Icon for the extension, compound ones like `tar.gz` fall back to their last part if they aren't known as a whole
+  pub fn from_extension(extension: &str) -> Self {
+    let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+    if let Some(icon) = custom_file_type(&extension) {
+      return icon
+    }
+
+    let icon = match extension.as_str() {
+      "txt" | "log" | "md" | "markdown" | "rst" | "rtf" | "doc" | "docx" | "odt" | "tex" => Self::Document,
+      "pdf" => Self::Pdf,
+      "csv" | "tsv" | "xls" | "xlsx" | "ods" => Self::Spreadsheet,
+      "ppt" | "pptx" | "odp" | "key" => Self::Presentation,
+      "json" | "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" | "env" | "lock" | "db" | "sqlite" => Self::Data,
+      "exe" | "msi" | "bat" | "cmd" | "sh" | "ps1" | "apk" | "app" | "appimage" => Self::Executable,
+      "zip" | "rar" | "7z" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "tar.gz" | "tar.bz2" | "tar.xz" | "tar.zst" | "jar" | "deb" | "rpm"
+      | "cab" => Self::Archive,
+      "iso" | "img" | "dmg" | "vhd" | "vhdx" => Self::DiskImage,
+      "jpg" | "jpeg" | "png" | "webp" | "gif" | "bmp" | "tif" | "tiff" | "ico" | "svg" | "heic" | "avif" | "psd" => Self::Image,
+      "mp4" | "webm" | "mkv" | "mov" | "avi" | "wmv" | "flv" | "m4v" | "mpg" | "mpeg" => Self::Video,
+      "mp3" | "wav" | "ogg" | "flac" | "aac" | "m4a" | "opus" | "wma" | "mid" | "midi" => Self::Audio,
+      "ttf" | "otf" | "woff" | "woff2" => Self::Font,
+      "xml" | "html" | "css" | "scss" | "js" | "ts" | "jsx" | "tsx" | "lua" | "rs" | "py" | "c" | "h" | "cpp" | "hpp" | "cs" | "java" | "kt"
+      | "go" | "rb" | "php" | "sql" | "swift" | "zig" => Self::Code,
+      _ => Self::Unknown
+    };
+    match extension.rsplit_once('.') {
+      Some((_, last)) if icon == Self::Unknown => Self::from_extension(last),
+      _ => icon
+    }
+  }
+
+  /// Icon for the MIME type, parameters like `; charset=utf-8` are ignored
+  pub fn from_mime(mime: &str) -> Self {
+    let mime = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    if let Some(icon) = custom_file_type(&mime) {
+      return icon
+    }
+
+    let (kind, subtype) = mime.split_once('/').unwrap_or((&mime, ""));
+    match (kind, subtype) {
+      (_, "pdf") => Self::Pdf,
+      (_, "csv" | "tab-separated-values" | "vnd.ms-excel") => Self::Spreadsheet,
+      (_, sub) if sub.contains("spreadsheet") => Self::Spreadsheet,
+      (_, "vnd.ms-powerpoint") => Self::Presentation,
+      (_, sub) if sub.contains("presentation") => Self::Presentation,
+      (_, "msword" | "rtf" | "markdown") => Self::Document,
+      (_, sub) if sub.contains("wordprocessing") || sub.contains("opendocument.text") => Self::Document,
+      ("application", "json" | "toml" | "yaml" | "x-yaml" | "vnd.sqlite3") => Self::Data,
+      (
+        "application",
+        "zip" | "gzip" | "x-tar" | "x-gtar" | "x-7z-compressed" | "vnd.rar" | "x-rar-compressed" | "x-bzip2" | "x-xz" | "zstd" | "java-archive"
+      ) => Self::Archive,
+      ("application", "x-iso9660-image" | "x-apple-diskimage") => Self::DiskImage,
+      ("application", "x-msdownload" | "x-executable" | "vnd.microsoft.portable-executable" | "x-sh" | "vnd.android.package-archive") => {
+        Self::Executable
+      },
+      ("application" | "text", "javascript" | "xml" | "html" | "css" | "x-python" | "x-rust" | "x-lua") => Self::Code,
+      ("image", _) => Self::Image,
+      ("video", _) => Self::Video,
+      ("audio", _) => Self::Audio,
+      ("font", _) => Self::Font,
+      ("text", _) => Self::Document,
       _ => Self::Unknown
     }
   }
 
   fn to_color(self) -> Rgba<u8> {
     match self {
-      FileIcon::Folder => to_rgba(0xFFD700),     // Gold
-      FileIcon::Document => to_rgba(0x4285F4),   // Blue
-      FileIcon::Image => to_rgba(0x34A853),      // Green
-      FileIcon::Video => to_rgba(0xEA4335),      // Red
-      FileIcon::Audio => to_rgba(0xFF6D01),      // Orange
-      FileIcon::Executable => to_rgba(0x9C27B0), // Purple
-      FileIcon::Archive => to_rgba(0x795548),    // Brown
-      FileIcon::Code => to_rgba(0xA7A7CA),       // Wistful
-      FileIcon::Unknown => to_rgba(0x9E9E9E)     // Grey
+      FileIcon::Folder => to_rgba(0xFFD700),       // Gold
+      FileIcon::Document => to_rgba(0x4285F4),     // Blue
+      FileIcon::Pdf => to_rgba(0xE53935),          // Crimson
+      FileIcon::Spreadsheet => to_rgba(0x1E8E3E),  // Dark green
+      FileIcon::Presentation => to_rgba(0xF4511E), // Deep orange
+      FileIcon::Data => to_rgba(0x00897B),         // Teal
+      FileIcon::Image => to_rgba(0x34A853),        // Green
+      FileIcon::Video => to_rgba(0xEA4335),        // Red
+      FileIcon::Audio => to_rgba(0xFF6D01),        // Orange
+      FileIcon::Executable => to_rgba(0x9C27B0),   // Purple
+      FileIcon::Archive => to_rgba(0x795548),      // Brown
+      FileIcon::DiskImage => to_rgba(0x607D8B),    // Slate
+      FileIcon::Font => to_rgba(0x3F51B5),         // Indigo
+      FileIcon::Code => to_rgba(0xA7A7CA),         // Wistful
+      FileIcon::Unknown => to_rgba(0x9E9E9E)       // Grey
+    }
+  }
+
+  /// Vector drawing of the icon filling the square `area`, drawn on a 16 unit grid
+  pub fn layer(
+    self,
+    area: Region
+  ) -> Layer {
+    let scale = area.width.min(area.height) as f32 / 16.0;
+    let at = |x: f32, y: f32| (area.x + (x * scale).round() as i32, area.y + (y * scale).round() as i32);
+    let len = |v: f32| (v * scale).round().max(1.0) as u32;
+    let color = self.to_color();
+    let white = to_rgba(0xFFFFFF);
+    let rect = |x: f32, y: f32, w: f32, h: f32, color: Rgba<u8>| Layer::Rect {
+      size:     (len(w), len(h)),
+      position: at(x, y),
+      fill:     color.into()
+    };
+    let polygon = |points: &[(f32, f32)], color: Rgba<u8>| Layer::Polygon {
+      points: points.iter().map(|(x, y)| at(*x, *y)).collect(),
+      fill:   color.into(),
+      border: None
+    };
+    let line = |from: (f32, f32), to: (f32, f32), color: Rgba<u8>| Layer::Line {
+      start: at(from.0, from.1),
+      end: at(to.0, to.1),
+      width: len(1.0),
+      color
+    };
+
+    if self == FileIcon::Folder {
+      let mut back = color;
+      back.0[0..3].iter_mut().for_each(|c| *c = (*c as f32 * 0.8) as u8);
+      return Layer::group(vec![
+        polygon(&[(1.0, 2.0), (6.0, 2.0), (7.5, 4.0), (15.0, 4.0), (15.0, 14.0), (1.0, 14.0)], back),
+        rect(1.0, 5.5, 14.0, 8.5, color),
+      ])
+    }
+
+    // sheet of paper with a folded corner, the mark of the type sits on its lower part
+    let page = [(2.5, 0.5), (10.0, 0.5), (13.5, 4.0), (13.5, 15.5), (2.5, 15.5)];
+    let mut layers = vec![
+      polygon(&page, to_rgba(0xF5F5F5)),
+      Layer::Polygon {
+        points: page.iter().map(|(x, y)| at(*x, *y)).collect(),
+        fill:   to_rgba(0x9E9E9E).into(),
+        border: Some(1)
+      },
+      polygon(&[(10.0, 0.5), (10.0, 4.0), (13.5, 4.0)], to_rgba(0xCFCFCF)),
+    ];
+    match self {
+      FileIcon::Document => {
+        for y in [6.0, 8.5, 11.0, 13.5] {
+          layers.push(line((4.5, y), (if y > 13.0 { 9.0 } else { 11.5 }, y), color));
+        }
+      },
+      FileIcon::Pdf | FileIcon::Presentation | FileIcon::Font => {
+        let label = match self {
+          FileIcon::Pdf => "PDF",
+          FileIcon::Presentation => "P",
+          _ => "Aa"
+        };
+        layers.push(rect(1.0, 8.0, 11.0, 6.0, color));
+        layers.push(
+          Layer::Text {
+            size:     5.5 * scale,
+            position: (0, 0),
+            color:    white,
+            content:  label.to_string(),
+            font:     LFont::RobotoBold
+          }
+          .anchored(Anchor::Center, at(6.5, 11.0))
+        );
+      },
+      FileIcon::Spreadsheet => {
+        layers.push(rect(4.0, 6.0, 8.0, 8.0, color));
+        layers.push(line((8.0, 6.0), (8.0, 14.0), white));
+        layers.push(line((4.0, 8.7), (12.0, 8.7), white));
+        layers.push(line((4.0, 11.4), (12.0, 11.4), white));
+      },
+      FileIcon::Data => {
+        // two sliders, like a settings panel
+        layers.push(line((4.5, 8.0), (11.5, 8.0), color));
+        layers.push(line((4.5, 12.0), (11.5, 12.0), color));
+        layers.push(rect(6.0, 6.5, 2.0, 3.0, color));
+        layers.push(rect(9.0, 10.5, 2.0, 3.0, color));
+      },
+      FileIcon::Executable => {
+        layers.push(rect(3.5, 6.0, 9.0, 7.5, color));
+        layers.push(rect(4.5, 8.0, 7.0, 4.5, white));
+      },
+      FileIcon::Archive => {
+        layers.push(rect(6.5, 0.5, 3.0, 15.0, color));
+        for y in [2.0, 5.0, 8.0] {
+          layers.push(rect(7.5, y, 1.0, 1.5, white));
+        }
+        layers.push(rect(6.5, 10.0, 3.0, 3.0, to_rgba(0xFFD54F)));
+      },
+      FileIcon::DiskImage => {
+        layers.push(Layer::Circle {
+          center: at(8.0, 10.0),
+          radius: len(4.5),
+          fill:   color.into(),
+          border: None
+        });
+        layers.push(Layer::Circle {
+          center: at(8.0, 10.0),
+          radius: len(1.2),
+          fill:   white.into(),
+          border: None
+        });
+      },
+      FileIcon::Image => {
+        layers.push(rect(4.0, 6.0, 8.0, 7.5, to_rgba(0xBBDEFB)));
+        layers.push(polygon(&[(4.0, 13.5), (7.0, 9.0), (9.0, 11.5), (10.0, 10.5), (12.0, 13.5)], color));
+        layers.push(Layer::Circle {
+          center: at(10.0, 8.0),
+          radius: len(1.0),
+          fill:   to_rgba(0xFBC02D).into(),
+          border: None
+        });
+      },
+      FileIcon::Video => {
+        layers.push(rect(3.5, 6.5, 9.0, 7.0, color));
+        layers.push(polygon(&[(6.8, 8.0), (10.0, 10.0), (6.8, 12.0)], white));
+      },
+      FileIcon::Audio => {
+        layers.push(Layer::Circle {
+          center: at(6.5, 12.5),
+          radius: len(1.8),
+          fill:   color.into(),
+          border: None
+        });
+        layers.push(line((8.0, 12.5), (8.0, 6.0), color));
+        layers.push(line((8.0, 6.0), (11.0, 7.5), color));
+      },
+      FileIcon::Code => {
+        layers.push(line((6.5, 7.5), (4.5, 10.0), color));
+        layers.push(line((4.5, 10.0), (6.5, 12.5), color));
+        layers.push(line((9.5, 7.5), (11.5, 10.0), color));
+        layers.push(line((11.5, 10.0), (9.5, 12.5), color));
+      },
+      FileIcon::Folder | FileIcon::Unknown => {}
     }
+    Layer::group(layers)
   }
 }
 
@@ -108,17 +489,34 @@ impl fmt::Display for FileIcon {
       FileIcon::Archive => "Archive",
       FileIcon::Audio => "Audio",
       FileIcon::Code => "Code",
+      FileIcon::Data => "Data File",
+      FileIcon::DiskImage => "Disc Image File",
       FileIcon::Document => "Text Document",
       FileIcon::Executable => "Application",
       FileIcon::Folder => "File folder",
+      FileIcon::Font => "Font File",
       FileIcon::Image => "Image",
+      FileIcon::Pdf => "PDF Document",
+      FileIcon::Presentation => "Presentation",
+      FileIcon::Spreadsheet => "Spreadsheet",
       FileIcon::Video => "Video",
-      _ => "File"
+      FileIcon::Unknown => "File"
     };
     write!(f, "{text}")
   }
 }
 
+/// Column the files are ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+  /// Case-insensitive name with the extension
+  Name,
+  Date,
+  Size,
+  /// Type name, then extension
+  Type
+}
+
 /// Style override options
 pub struct Style {
   pub theme:            Theme,
@@ -126,7 +524,17 @@ pub struct Style {
   pub font_size:        f32,
   pub header_font_size: f32,
   pub row_height:       u32,
-  pub padding:          u32
+  pub padding:          u32,
+  /// Defaults to `None`, keeping the given order
+  pub sort_by:          Option<SortBy>,
+  /// Reverses [Style::sort_by], folders stay on top if [Style::folders_first] is set<br>
+  /// Defaults to `false`
+  pub descending:       bool,
+  /// Defaults to `true`
+  pub folders_first:    bool,
+  /// Bar at the bottom with the item count and total size<br>
+  /// Defaults to `true`
+  pub show_status_bar:  bool
 }
 
 impl Default for Style {
@@ -137,7 +545,11 @@ impl Default for Style {
       font_size:        12.0,
       header_font_size: 14.0,
       row_height:       22,
-      padding:          8
+      padding:          8,
+      sort_by:          None,
+      descending:       false,
+      folders_first:    true,
+      show_status_bar:  true
     }
   }
 }
@@ -215,7 +627,8 @@ pub enum Theme {
   Dark
 }
 
-/// Mimics the Windows 10 File Explorer design
+/// Mimics the Windows 10 File Explorer design<br>
+/// Files are ordered by [Style::sort_by] and [Style::folders_first], `highlighted_index` points into `files` as given
 pub fn file_explorer(
   current_path: &str,
   files: &[Metadata],
@@ -231,8 +644,11 @@ pub fn file_explorer(
   const TOOLBAR_HEIGHT: u32 = 35;
   const COLUMN_HEADER_HEIGHT: u32 = 24;
   const ICON_SIZE: u32 = 16;
+  const STATUS_BAR_HEIGHT: u32 = 22;
 
-  let total_height = HEADER_HEIGHT + TOOLBAR_HEIGHT + COLUMN_HEADER_HEIGHT + (files.len() as u32 * style.row_height) + style.padding * 2;
+  let status_bar_height = if style.show_status_bar { STATUS_BAR_HEIGHT } else { 0 };
+  let total_height =
+    HEADER_HEIGHT + TOOLBAR_HEIGHT + COLUMN_HEADER_HEIGHT + (files.len() as u32 * style.row_height) + style.padding * 2 + status_bar_height;
 
   let mut canvas = Canvas::new(width, total_height);
   canvas.set_bg_color(style.bg_color());
@@ -241,12 +657,12 @@ pub fn file_explorer(
   canvas.add_layer(Layer::Rect {
     size:     (width, HEADER_HEIGHT),
     position: (0, 0),
-    color:    style.header_bar_color()
+    fill:     style.header_bar_color().into()
   });
 
   canvas.add_layer(Layer::Text {
     size:     style.header_font_size,
-    position: (style.padding, 12),
+    position: (style.padding as i32, 12),
     color:    style.header_text_color(),
     content:  "File Explorer".to_string(),
     font:     style.font
@@ -255,21 +671,21 @@ pub fn file_explorer(
   // toolbar bg
   canvas.add_layer(Layer::Rect {
     size:     (width, TOOLBAR_HEIGHT),
-    position: (0, HEADER_HEIGHT),
-    color:    style.toolbar_color()
+    position: (0, HEADER_HEIGHT as i32),
+    fill:     style.toolbar_color().into()
   });
 
   // back button
   if show_back_btn {
     canvas.add_layer(Layer::Rect {
       size:     (60, 25),
-      position: (style.padding, HEADER_HEIGHT + 5),
-      color:    style.border_color()
+      position: (style.padding as i32, (HEADER_HEIGHT + 5) as i32),
+      fill:     style.border_color().into()
     });
 
     canvas.add_layer(Layer::Text {
       size:     style.font_size,
-      position: (style.padding + 15, HEADER_HEIGHT + 12),
+      position: ((style.padding + 15) as i32, (HEADER_HEIGHT + 12) as i32),
       color:    style.text_color(),
       content:  "Back".to_string(),
       font:     style.font
@@ -281,197 +697,209 @@ pub fn file_explorer(
   let address_w = width - address_x - style.padding;
   canvas.add_layer(Layer::Rect {
     size:     (address_w, 25),
-    position: (address_x, HEADER_HEIGHT + 5),
-    color:    style.bg_color()
+    position: (address_x as i32, (HEADER_HEIGHT + 5) as i32),
+    fill:     style.bg_color().into()
   });
 
   // address bar's border
   canvas.add_layer(Layer::Line {
-    start: (address_x, HEADER_HEIGHT + 5),
-    end:   (address_x + address_w, HEADER_HEIGHT + 5),
+    start: (address_x as i32, (HEADER_HEIGHT + 5) as i32),
+    end:   ((address_x + address_w) as i32, (HEADER_HEIGHT + 5) as i32),
     width: 1,
     color: style.border_color()
   });
 
   canvas.add_layer(Layer::Line {
-    start: (address_x, HEADER_HEIGHT + 30),
-    end:   (address_x + address_w, HEADER_HEIGHT + 30),
+    start: (address_x as i32, (HEADER_HEIGHT + 30) as i32),
+    end:   ((address_x + address_w) as i32, (HEADER_HEIGHT + 30) as i32),
     width: 1,
     color: style.border_color()
   });
 
   canvas.add_layer(Layer::Text {
     size:     style.font_size,
-    position: (address_x + 5, HEADER_HEIGHT + 12),
+    position: ((address_x + 5) as i32, (HEADER_HEIGHT + 12) as i32),
     color:    style.text_color(),
     content:  current_path.to_string(),
     font:     style.font
   });
 
-  // column headers
+  // file list
+  let table_style = TableStyle {
+    bg_color: style.bg_color(),
+    header_color: style.header_bar_color(),
+    header_text_color: style.header_text_color(),
+    row_colors: [style.bg_color(), style.alt_row_color()],
+    text_color: style.text_color(),
+    highlight_color: style.highlight_color(),
+    highlight_text_color: style.highlight_text_color(),
+    separator_color: Some(style.border_color()),
+    font: style.font,
+    header_font: style.font,
+    font_size: style.font_size,
+    header_font_size: style.font_size,
+    width,
+    header_height: COLUMN_HEADER_HEIGHT,
+    row_height: style.row_height,
+    padding: style.padding,
+    icon_size: ICON_SIZE
+  };
+  let order = sort_order(files, &style);
+  let rows: Vec<Metadata> = order.iter().map(|i| files[*i].clone()).collect();
+  let highlighted = highlighted_index.and_then(|index| order.iter().position(|i| *i == index));
   let header_y = HEADER_HEIGHT + TOOLBAR_HEIGHT;
-  canvas.add_layer(Layer::Rect {
-    size:     (width, COLUMN_HEADER_HEIGHT),
-    position: (0, header_y),
-    color:    style.header_bar_color()
-  });
-
-  // column widths
-  let name_w = width * 40 / 100;
-  let date_w = width * 30 / 100;
-  let type_w = width * 20 / 100;
-
-  // column header texts
-  canvas.add_layer(Layer::Text {
-    size:     style.font_size,
-    position: (style.padding + ICON_SIZE + 5, header_y + 6),
-    color:    style.header_text_color(),
-    content:  "Name".to_string(),
-    font:     style.font
-  });
-
-  canvas.add_layer(Layer::Text {
-    size:     style.font_size,
-    position: (name_w + 5, header_y + 6),
-    color:    style.header_text_color(),
-    content:  "Date modified".to_string(),
-    font:     style.font
-  });
-
-  canvas.add_layer(Layer::Text {
-    size:     style.font_size,
-    position: (name_w + date_w + 5, header_y + 6),
-    color:    style.header_text_color(),
-    content:  "Type".to_string(),
-    font:     style.font
-  });
-
-  canvas.add_layer(Layer::Text {
-    size:     style.font_size,
-    position: (name_w + date_w + type_w + 5, header_y + 6),
-    color:    style.header_text_color(),
-    content:  "Size".to_string(),
-    font:     style.font
-  });
-
-  // column separators
-  canvas.add_layer(Layer::Line {
-    start: (name_w, header_y),
-    end:   (name_w, header_y + COLUMN_HEADER_HEIGHT),
-    width: 1,
-    color: style.border_color()
-  });
-
-  canvas.add_layer(Layer::Line {
-    start: (name_w + date_w, header_y),
-    end:   (name_w + date_w, header_y + COLUMN_HEADER_HEIGHT),
-    width: 1,
-    color: style.border_color()
-  });
-
-  canvas.add_layer(Layer::Line {
-    start: (name_w + date_w + type_w, header_y),
-    end:   (name_w + date_w + type_w, header_y + COLUMN_HEADER_HEIGHT),
-    width: 1,
-    color: style.border_color()
-  });
-
-  // rows for file/folder
-  let content_start_y = header_y + COLUMN_HEADER_HEIGHT;
-  for (i, f) in files.iter().enumerate() {
-    let row_y = content_start_y + (i as u32 * style.row_height);
-    let is_highlighted = highlighted_index == Some(i);
-
-    // row bg
-    let (row_color, text_color) = if is_highlighted {
-      (style.highlight_color(), style.highlight_text_color())
-    } else if i % 2 == 1 {
-      (style.alt_row_color(), style.text_color())
-    } else {
-      (style.bg_color(), style.text_color())
-    };
+  draw_table(&mut canvas, (0, header_y as i32), &rows, &columns(), highlighted.as_slice(), &table_style);
 
+  // status bar
+  if style.show_status_bar {
+    let bar_y = (total_height - STATUS_BAR_HEIGHT) as i32;
     canvas.add_layer(Layer::Rect {
-      size:     (width, style.row_height),
-      position: (0, row_y),
-      color:    row_color
+      size:     (width, STATUS_BAR_HEIGHT),
+      position: (0, bar_y),
+      fill:     style.toolbar_color().into()
     });
-
-    // icon
-    canvas.add_layer(Layer::Rect {
-      size:     (ICON_SIZE, ICON_SIZE),
-      position: (style.padding, row_y + 3),
-      color:    f.icon.to_color()
+    canvas.add_layer(Layer::Line {
+      start: (0, bar_y),
+      end:   (width as i32, bar_y),
+      width: 1,
+      color: style.border_color()
     });
 
-    // name
-    let name = if f.extension.is_empty() || f.is_folder {
-      f.name.clone()
-    } else {
-      format!("{}.{}", f.name, f.extension)
-    };
-
-    canvas.add_layer(Layer::Text {
-      size:     style.font_size,
-      position: (style.padding + ICON_SIZE + 5, row_y + 5),
-      color:    text_color,
-      content:  name,
-      font:     style.font
-    });
+    let count = |n: usize| if n == 1 { "1 item".to_string() } else { format!("{n} items") };
+    let total: u64 = files.iter().filter_map(|f| f.size).sum();
+    let mut status = count(files.len());
+    if total > 0 {
+      status.push_str(&format!("    {}", format_bytes(total)));
+    }
+    if let Some(selected) = highlighted_index.and_then(|i| files.get(i)) {
+      status.push_str(&format!("    {} selected", count(1)));
+      if let Some(size) = selected.size {
+        status.push_str(&format!("  {}", format_bytes(size)));
+      }
+    }
+    canvas.add_layer(
+      Layer::Text {
+        size:     style.font_size,
+        position: (0, 0),
+        color:    style.text_color(),
+        content:  status,
+        font:     style.font
+      }
+      .anchored(Anchor::Left, (style.padding as i32, bar_y + STATUS_BAR_HEIGHT as i32 / 2))
+    );
+  }
 
-    // date modified text
-    canvas.add_layer(Layer::Text {
-      size:     style.font_size,
-      position: (name_w + 5, row_y + 5),
-      color:    text_color,
-      content:  f.date.clone(),
-      font:     style.font
-    });
+  canvas
+}
 
-    // type text
-    canvas.add_layer(Layer::Text {
-      size:     style.font_size,
-      position: (name_w + date_w + 5, row_y + 5),
-      color:    text_color,
-      content:  f.icon.to_string(),
-      font:     style.font
-    });
+/// Lists the folder with [read_dir] and renders it, the back button shows unless it's a root
+pub fn file_explorer_dir(
+  path: impl AsRef<Path>,
+  width: u32,
+  options: &ReadOptions,
+  style: Option<Style>
+) -> AsahiResult<Canvas> {
+  let path = path.as_ref();
+  let files = read_dir(path, options)?;
+  Ok(file_explorer(
+    &path.display().to_string(),
+    &files,
+    width,
+    path.parent().is_some(),
+    None,
+    style
+  ))
+}
 
-    // size text
-    let size_text = if let Some(size) = f.size {
-      format_bytes(size)
+/// Indices of the files in the order they're listed
+fn sort_order(
+  files: &[Metadata],
+  style: &Style
+) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..files.len()).collect();
+  order.sort_by(|a, b| {
+    let (a, b) = (&files[*a], &files[*b]);
+    let group = if style.folders_first {
+      b.is_folder.cmp(&a.is_folder)
     } else {
-      String::new() // for folders
+      Ordering::Equal
+    };
+    let by = match style.sort_by {
+      None => Ordering::Equal,
+      Some(SortBy::Name) => a.file_name().to_lowercase().cmp(&b.file_name().to_lowercase()),
+      // files without a timestamp go last, ordered by their date text among themselves
+      Some(SortBy::Date) => (a.modified.is_none(), a.modified)
+        .cmp(&(b.modified.is_none(), b.modified))
+        .then_with(|| if a.modified.is_none() { a.date.cmp(&b.date) } else { Ordering::Equal }),
+      Some(SortBy::Size) => a.size.unwrap_or(0).cmp(&b.size.unwrap_or(0)),
+      Some(SortBy::Type) => a
+        .icon
+        .to_string()
+        .cmp(&b.icon.to_string())
+        .then_with(|| a.extension.to_lowercase().cmp(&b.extension.to_lowercase()))
     };
+    group.then(if style.descending { by.reverse() } else { by })
+  });
+  order
+}
 
-    canvas.add_layer(Layer::Text {
-      size:     style.font_size,
-      position: (name_w + date_w + type_w + 5, row_y + 5),
-      color:    text_color,
-      content:  size_text,
-      font:     style.font
+/// Icon of the file with a shortcut arrow for symlinks, faded if it's hidden
+fn file_icon(
+  file: &Metadata,
+  area: Region
+) -> Layer {
+  let mut layers = vec![file.icon.layer(area)];
+  if file.is_symlink {
+    let size = (area.width / 2).max(6);
+    let (x, y) = (area.x, area.bottom() - size as i32);
+    let at = |fx: f32, fy: f32| (x + (fx * size as f32).round() as i32, y + (fy * size as f32).round() as i32);
+    layers.push(Layer::Rect {
+      size:     (size, size),
+      position: (x, y),
+      fill:     to_rgba(0xFFFFFF).into()
+    });
+    layers.push(Layer::Line {
+      start: at(0.25, 0.8),
+      end:   at(0.75, 0.3),
+      width: (size / 6).max(1),
+      color: to_rgba(0x0078D4)
+    });
+    layers.push(Layer::Polygon {
+      points: vec![at(0.4, 0.2), at(0.85, 0.15), at(0.8, 0.6)],
+      fill:   to_rgba(0x0078D4).into(),
+      border: None
     });
   }
 
-  canvas
+  let layer = Layer::group(layers);
+  if file.is_hidden { layer.with_opacity(0.5) } else { layer }
+}
+
+/// Name, date modified and type columns at 40, 30 and 20% of the width, size takes the rest
+fn columns() -> Vec<Column<Metadata>> {
+  vec![
+    Column::new("Name", Metadata::file_name)
+      .with_width(ColumnWidth::Percent(40.0))
+      .with_icon(file_icon),
+    Column::new("Date modified", |f: &Metadata| f.date.clone()).with_width(ColumnWidth::Percent(30.0)),
+    Column::new("Type", |f: &Metadata| f.icon.to_string()).with_width(ColumnWidth::Percent(20.0)),
+    // folders leave it empty
+    Column::new("Size", |f: &Metadata| f.size.map(format_bytes).unwrap_or_default()).with_align(Align::Right),
+  ]
 }
 
 #[cfg(test)]
 mod test {
   use {
     super::*,
-    crate::ImageFormat
+    crate::snapshot::assert_snapshot
   };
 
   #[test]
   fn test_file_explorer_empty() {
     let files = [];
     let canvas = file_explorer("C:\\Users\\asahi\\Desktop", &files, 600, false, None, None);
-    std::fs::write(
-      "file_explorer_empty_test.jpg",
-      canvas.to_bytes(Some(ImageFormat::Jpeg { quality: 100 })).unwrap()
-    )
-    .unwrap();
+    assert_snapshot("file_explorer_empty", &canvas);
   }
 
   #[test]
@@ -493,11 +921,7 @@ mod test {
         ..Default::default()
       })
     );
-    std::fs::write(
-      "file_explorer_test.jpg",
-      canvas.to_bytes(Some(ImageFormat::Jpeg { quality: 100 })).unwrap()
-    )
-    .unwrap();
+    assert_snapshot("file_explorer", &canvas);
   }
 
   #[test]
@@ -507,10 +931,202 @@ mod test {
       Metadata::new_file("test4".to_string(), "29/07/2019 12:21".to_string(), "txt".to_string(), 8192)
     ];
     let canvas = file_explorer("C:\\Test", &files, 600, false, None, None);
-    std::fs::write(
-      "file_explorer_test_bytes.jpg",
-      canvas.to_bytes(Some(ImageFormat::Jpeg { quality: 100 })).unwrap()
-    )
-    .unwrap();
+    assert_snapshot("file_explorer_bytes", &canvas);
+  }
+
+  #[test]
+  fn test_file_types() {
+    let icons: Vec<_> = ["pdf", "JSON", "toml", "md", "tar.gz", "backup.xz", "flac", ".iso", "nope"]
+      .iter()
+      .map(|e| FileIcon::from_extension(e))
+      .collect();
+    assert_eq!(
+      icons,
+      [
+        FileIcon::Pdf,
+        FileIcon::Data,
+        FileIcon::Data,
+        FileIcon::Document,
+        FileIcon::Archive,
+        FileIcon::Archive,
+        FileIcon::Audio,
+        FileIcon::DiskImage,
+        FileIcon::Unknown
+      ]
+    );
+    assert_eq!(FileIcon::from_mime("text/plain; charset=utf-8"), FileIcon::Document);
+    assert_eq!(
+      FileIcon::from_mime("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+      FileIcon::Spreadsheet
+    );
+
+    register_file_type(".asahiproj", FileIcon::Code);
+    register_file_type("application/x-asahi", FileIcon::Data);
+    assert_eq!(FileIcon::from_extension("AsahiProj"), FileIcon::Code);
+    assert_eq!(FileIcon::from_mime("application/x-asahi"), FileIcon::Data);
+  }
+
+  #[test]
+  fn test_sort_order() {
+    let mut files = vec![
+      Metadata::new_file("b".to_string(), "02/01/2025 10:00".to_string(), "txt".to_string(), 300),
+      Metadata::new_folder("z".to_string(), "01/01/2025 10:00".to_string()),
+      Metadata::new_file("A".to_string(), "03/01/2025 10:00".to_string(), "png".to_string(), 100),
+    ];
+    let order = |files: &[Metadata], sort_by, descending, folders_first| {
+      let style = Style {
+        sort_by,
+        descending,
+        folders_first,
+        ..Default::default()
+      };
+      sort_order(files, &style)
+    };
+    assert_eq!(order(&files, None, false, true), [1, 0, 2]);
+    assert_eq!(order(&files, Some(SortBy::Name), false, false), [2, 0, 1]);
+    assert_eq!(order(&files, Some(SortBy::Size), true, true), [1, 0, 2]);
+
+    files[0].modified = Some(10);
+    files[2].modified = Some(5);
+    assert_eq!(order(&files, Some(SortBy::Date), false, true), [1, 2, 0]);
+
+    // timestamps first, the rest by their date text
+    files.push(Metadata::new_file("c".to_string(), "00/01/2025 10:00".to_string(), "txt".to_string(), 1));
+    assert_eq!(order(&files, Some(SortBy::Date), false, false), [2, 0, 3, 1]);
+  }
+
+  #[test]
+  fn test_file_explorer_sorted() {
+    let files = [
+      ("notes", "md", 2_048),
+      ("report", "pdf", 1_258_291),
+      ("config", "toml", 512),
+      ("release", "tar.gz", 48_234_496),
+      ("track01", "flac", 31_457_280),
+      ("budget", "xlsx", 20_480),
+      ("setup", "exe", 5_242_880),
+      ("main", "rs", 8_192)
+    ]
+    .into_iter()
+    .map(|(name, extension, size)| Metadata::new_file(name.to_string(), "03/06/2025 11:07".to_string(), extension.to_string(), size))
+    .chain([
+      Metadata::new_folder("Projects".to_string(), "17/02/2025 18:30".to_string()),
+      Metadata {
+        is_symlink: true,
+        ..Metadata::new_folder("Shared".to_string(), "20/02/2025 09:12".to_string())
+      },
+      Metadata {
+        is_hidden: true,
+        ..Metadata::new_file(".gitignore".to_string(), "01/03/2025 08:00".to_string(), String::new(), 120)
+      }
+    ])
+    .collect::<Vec<_>>();
+    let style = Style {
+      sort_by: Some(SortBy::Size),
+      descending: true,
+      ..Style::dark()
+    };
+
+    assert_snapshot(
+      "file_explorer_sorted",
+      &file_explorer("D:\\Downloads", &files, 600, true, Some(1), Some(style))
+    );
+  }
+
+  #[test]
+  fn test_split_extension() {
+    let split = |name| {
+      let (name, extension) = split_extension(name);
+      format!("{name}|{extension}")
+    };
+    assert_eq!(split("notes.md"), "notes|md");
+    assert_eq!(split("release.tar.gz"), "release|tar.gz");
+    assert_eq!(split("my.file.txt"), "my.file|txt");
+    assert_eq!(split(".env"), ".env|");
+    assert_eq!(split("README"), "README|");
+  }
+
+  #[test]
+  fn test_read_dir() {
+    let dir = std::env::temp_dir().join(format!("asahi_explorer_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(dir.join("notes.md"), [0; 1500]).unwrap();
+    fs::write(dir.join("release.tar.gz"), []).unwrap();
+    fs::write(dir.join(".secret"), []).unwrap();
+    let new_year = UNIX_EPOCH + std::time::Duration::from_secs(1_735_689_600);
+    fs::File::options()
+      .write(true)
+      .open(dir.join("notes.md"))
+      .unwrap()
+      .set_modified(new_year)
+      .unwrap();
+    #[cfg(unix)]
+    {
+      std::os::unix::fs::symlink(dir.join("notes.md"), dir.join("shortcut")).unwrap();
+      std::os::unix::fs::symlink(dir.join("missing"), dir.join("broken")).unwrap();
+    }
+
+    let files = read_dir(&dir, &ReadOptions::default()).unwrap();
+    let names: Vec<_> = files.iter().map(Metadata::file_name).collect();
+    if cfg!(unix) {
+      assert_eq!(names, ["broken", "docs", "notes.md", "release.tar.gz", "shortcut"]);
+      let shortcut = &files[4];
+      assert!(shortcut.is_symlink && shortcut.size == Some(1500));
+      assert!(files[0].is_symlink && files[0].icon == FileIcon::Unknown);
+    } else {
+      assert_eq!(names, ["docs", "notes.md", "release.tar.gz"]);
+    }
+
+    let notes = files.iter().find(|f| f.name == "notes").unwrap();
+    assert_eq!((notes.extension.as_str(), notes.size, notes.icon), ("md", Some(1500), FileIcon::Document));
+    assert_eq!((notes.date.as_str(), notes.modified), ("01/01/2025 00:00", Some(1_735_689_600)));
+    assert!(files.iter().find(|f| f.name == "docs").unwrap().is_folder);
+    assert_eq!(files.iter().find(|f| f.name == "release").unwrap().icon, FileIcon::Archive);
+
+    let options = ReadOptions {
+      date_pattern:    "%Y".to_string(),
+      follow_symlinks: false,
+      show_hidden:     true
+    };
+    let files = read_dir(&dir, &options).unwrap();
+    let secret = files.iter().find(|f| f.name == ".secret").unwrap();
+    assert!(secret.is_hidden && secret.extension.is_empty());
+    assert_eq!(files.iter().find(|f| f.name == "notes").unwrap().date, "2025");
+    #[cfg(unix)]
+    assert_ne!(files.iter().find(|f| f.name == "shortcut").unwrap().size, Some(1500));
+
+    let canvas = file_explorer_dir(&dir, 600, &options, None).unwrap();
+    assert_eq!(canvas.width, 600);
+    assert!(Metadata::from_path(dir.join("missing"), &options).is_err());
+    assert!(read_dir(dir.join("missing"), &options).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_file_icons() {
+    let icons = [
+      FileIcon::Folder,
+      FileIcon::Document,
+      FileIcon::Pdf,
+      FileIcon::Spreadsheet,
+      FileIcon::Presentation,
+      FileIcon::Data,
+      FileIcon::Executable,
+      FileIcon::Archive,
+      FileIcon::DiskImage,
+      FileIcon::Image,
+      FileIcon::Video,
+      FileIcon::Audio,
+      FileIcon::Font,
+      FileIcon::Code,
+      FileIcon::Unknown
+    ];
+    let mut canvas = Canvas::new(icons.len() as u32 * 56 + 8, 64);
+    canvas.set_bg_color(to_rgba(0xFFFFFF));
+    for (i, icon) in icons.iter().enumerate() {
+      canvas.add_layer(icon.layer(Region::new(8 + i as i32 * 56, 8, 48, 48)));
+    }
+    assert_snapshot("file_icons", &canvas);
   }
 }
@@ -0,0 +1,314 @@
+use {
+  super::text,
+  crate::{
+    canvas::{
+      Canvas,
+      assume_text_width
+    },
+    layer::{
+      Anchor,
+      Font as LFont,
+      Layer
+    },
+    rich_text::RichText,
+    transform::ImageTransform
+  },
+  asahi_utils::format_compact,
+  image::{
+    DynamicImage,
+    Rgba
+  }
+};
+
+/// Leaderboard entry, everything past the name and value is optional
+#[derive(Default)]
+pub struct LeaderboardEntry {
+  /// Defaults to the position in the list, starting at 1
+  pub rank:     Option<u32>,
+  pub name:     String,
+  /// Drawn as a circle before the name
+  pub avatar:   Option<DynamicImage>,
+  pub value:    f64,
+  /// Fill of the bar under the name from `0.0` to `1.0`<br>
+  /// Defaults to the value relative to the highest one
+  pub progress: Option<f32>
+}
+
+impl LeaderboardEntry {
+  pub fn new(
+    name: impl Into<String>,
+    value: f64
+  ) -> Self {
+    Self {
+      name: name.into(),
+      value,
+      ..Default::default()
+    }
+  }
+}
+
+/// Style override options, start from [Style::dark] (the default) or [Style::light]
+pub struct Style {
+  /// Text in the header<br>
+  /// Defaults to **Leaderboard**
+  pub title:            String,
+  /// Text to display if there are no entries<br>
+  /// Defaults to **Nobody ranked yet**
+  pub empty_text:       String,
+  pub bg_color:         Rgba<u8>,
+  pub header_bar_color: Rgba<u8>,
+  /// Background of the even and odd rows
+  pub row_colors:       [Rgba<u8>; 2],
+  pub text_color:       Rgba<u8>,
+  /// Colour of the ranks past the top 3
+  pub muted_color:      Rgba<u8>,
+  pub bar_color:        Rgba<u8>,
+  pub bar_track_color:  Rgba<u8>,
+  /// Colours of the first, second and third place, used for their rank badge and value
+  pub highlight_colors: [Rgba<u8>; 3],
+  /// Colour of the rank written on a highlighted badge
+  pub badge_text_color: Rgba<u8>,
+  pub font:             LFont,
+  /// Used for the title, ranks and values
+  pub bold_font:        LFont,
+  pub font_size:        f32,
+  pub header_font_size: f32,
+  /// Defaults to `600`
+  pub width:            u32,
+  /// Defaults to `50`
+  pub header_height:    u32,
+  /// Defaults to `64`
+  pub row_height:       u32,
+  pub padding:          u32,
+  /// Turns the values into their label<br>
+  /// Defaults to [format_compact], e.g **12.3k**
+  pub format_value:     fn(f64) -> String
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Self {
+      title:            String::from("Leaderboard"),
+      empty_text:       String::from("Nobody ranked yet"),
+      bg_color:         Rgba([5, 5, 5, 255]),
+      header_bar_color: Rgba([10, 10, 10, 255]),
+      row_colors:       [Rgba([10, 10, 10, 255]), Rgba([20, 20, 20, 255])],
+      text_color:       Rgba([255, 255, 255, 255]),
+      muted_color:      Rgba([148, 155, 164, 255]),
+      bar_color:        Rgba([201, 55, 93, 255]),
+      bar_track_color:  Rgba([255, 255, 255, 30]),
+      highlight_colors: [Rgba([241, 196, 15, 255]), Rgba([189, 195, 199, 255]), Rgba([205, 127, 50, 255])],
+      badge_text_color: Rgba([20, 20, 20, 255]),
+      font:             LFont::UbuntuRegular,
+      bold_font:        LFont::UbuntuBold,
+      font_size:        22.0,
+      header_font_size: 32.0,
+      width:            600,
+      header_height:    50,
+      row_height:       64,
+      padding:          12,
+      format_value:     format_compact
+    }
+  }
+}
+
+impl Style {
+  /// Light text on a near black background
+  pub fn dark() -> Self { Self::default() }
+
+  /// Dark text on a white background
+  pub fn light() -> Self {
+    Self {
+      bg_color: Rgba([255, 255, 255, 255]),
+      header_bar_color: Rgba([240, 240, 240, 255]),
+      row_colors: [Rgba([250, 250, 250, 255]), Rgba([238, 238, 238, 255])],
+      text_color: Rgba([30, 31, 34, 255]),
+      muted_color: Rgba([92, 94, 102, 255]),
+      bar_track_color: Rgba([0, 0, 0, 25]),
+      highlight_colors: [Rgba([230, 176, 0, 255]), Rgba([160, 167, 173, 255]), Rgba([196, 113, 40, 255])],
+      ..Default::default()
+    }
+  }
+}
+
+/// Renders the entries in the given order, one row each
+pub fn leaderboard(
+  entries: &[LeaderboardEntry],
+  style: Option<Style>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let rows = entries.len().max(1) as u32;
+  let width = style.width;
+  let height = style.header_height + rows * style.row_height + style.padding;
+  let mut canvas = Canvas::new(width, height);
+  canvas.set_bg_color(style.bg_color);
+
+  canvas.add_layer(Layer::Rect {
+    size:     (width, style.header_height),
+    position: (0, 0),
+    fill:     style.header_bar_color.into()
+  });
+  canvas.add_layer(
+    text(&style.title, style.header_font_size, style.text_color, style.bold_font)
+      .anchored(Anchor::Center, (width as i32 / 2, style.header_height as i32 / 2))
+  );
+
+  if entries.is_empty() {
+    canvas.add_layer(
+      text(&style.empty_text, style.font_size, style.muted_color, style.font)
+        .anchored(Anchor::Center, (width as i32 / 2, (style.header_height + style.row_height / 2) as i32))
+    );
+    return canvas
+  }
+
+  let bold = style.bold_font.to_fontarc();
+  let pad = style.padding as i32;
+  let labels: Vec<String> = entries.iter().map(|e| (style.format_value)(e.value)).collect();
+  let value_width = labels.iter().map(|l| assume_text_width(l, style.font_size, &bold)).max().unwrap_or(0) as i32;
+  let top_value = entries.iter().map(|e| e.value).filter(|v| v.is_finite()).fold(0.0, f64::max);
+
+  let badge = (style.row_height as f32 * 0.3).round() as u32;
+  let avatar_size = style.row_height.saturating_sub(style.padding * 2);
+  let has_avatars = entries.iter().any(|e| e.avatar.is_some());
+  let name_x = pad * 2 + badge as i32 * 2 + if has_avatars { avatar_size as i32 + pad } else { 0 };
+  let value_x = width as i32 - pad * 2;
+  let bar_width = (value_x - value_width - pad * 2 - name_x).max(0) as u32;
+  let bar_height = (style.row_height / 8).max(4);
+
+  for (i, (entry, label)) in entries.iter().zip(labels).enumerate() {
+    let y = (style.header_height + i as u32 * style.row_height) as i32;
+    let middle = y + style.row_height as i32 / 2;
+    let rank = entry.rank.unwrap_or(i as u32 + 1);
+    let highlight = rank.checked_sub(1).and_then(|r| style.highlight_colors.get(r as usize)).copied();
+
+    canvas.add_layer(Layer::Rect {
+      size:     (width, style.row_height),
+      position: (0, y),
+      fill:     style.row_colors[i % 2].into()
+    });
+
+    let badge_center = (pad + badge as i32, middle);
+    let rank_color = match highlight {
+      Some(color) => {
+        canvas.add_layer(Layer::Circle {
+          center: badge_center,
+          radius: badge,
+          fill:   color.into(),
+          border: None
+        });
+        style.badge_text_color
+      },
+      None => style.muted_color
+    };
+    canvas.add_layer(text(&rank.to_string(), style.font_size * 0.85, rank_color, style.bold_font).anchored(Anchor::Center, badge_center));
+
+    if let Some(avatar) = &entry.avatar {
+      canvas.add_layer(Layer::Image {
+        scale:     1.0,
+        position:  (pad * 2 + badge as i32 * 2, y + style.padding as i32),
+        image:     avatar.clone(),
+        transform: ImageTransform::avatar(avatar_size)
+      });
+    }
+
+    canvas.add_layer(
+      RichText::new(entry.name.as_str(), style.font_size, style.text_color, style.font)
+        .lay_out()
+        .truncated(bar_width)
+        .layer((0, 0))
+        .anchored(Anchor::BottomLeft, (name_x, middle + 2))
+    );
+
+    let fraction = entry
+      .progress
+      .unwrap_or(if top_value > 0.0 { (entry.value / top_value) as f32 } else { 0.0 });
+    canvas.add_layer(progress_bar(
+      (name_x, middle + bar_height as i32),
+      (bar_width, bar_height),
+      fraction,
+      style.bar_track_color,
+      highlight.unwrap_or(style.bar_color)
+    ));
+
+    canvas
+      .add_layer(text(&label, style.font_size, highlight.unwrap_or(style.text_color), style.bold_font).anchored(Anchor::Right, (value_x, middle)));
+  }
+
+  canvas
+}
+
+/// Rounded bar filled from the left by `fraction`, clamped between `0.0` and `1.0`
+pub(super) fn progress_bar(
+  position: (i32, i32),
+  size: (u32, u32),
+  fraction: f32,
+  track: Rgba<u8>,
+  fill: Rgba<u8>
+) -> Layer {
+  let radius = size.1 / 2;
+  let mut layers = vec![Layer::RoundedRect {
+    size,
+    position,
+    radius,
+    fill: track.into(),
+    border: None
+  }];
+
+  let filled = (size.0 as f32 * fraction.clamp(0.0, 1.0)).round() as u32;
+  if filled > 0 {
+    layers.push(Layer::RoundedRect {
+      size: (filled.max(size.1), size.1),
+      position,
+      radius,
+      fill: fill.into(),
+      border: None
+    });
+  }
+  Layer::group(layers)
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot,
+    image::RgbaImage
+  };
+
+  #[test]
+  fn test_snapshot() {
+    let avatar = |tint: u8| DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, tint, 255])));
+    let entries = [
+      LeaderboardEntry {
+        avatar: Some(avatar(220)),
+        ..LeaderboardEntry::new("Nwero", 15_320.0)
+      },
+      LeaderboardEntry {
+        avatar: Some(avatar(120)),
+        ..LeaderboardEntry::new("Daggerwin", 12_100.0)
+      },
+      LeaderboardEntry {
+        avatar: Some(avatar(40)),
+        ..LeaderboardEntry::new("Somebody with a rather long name", 9_870.0)
+      },
+      LeaderboardEntry::new("Mr. Pallet", 4_410.0),
+      LeaderboardEntry {
+        rank: Some(12),
+        progress: Some(0.9),
+        ..LeaderboardEntry::new("Test2", 950.0)
+      }
+    ];
+
+    assert_snapshot("leaderboard", &leaderboard(&entries, None));
+  }
+
+  #[test]
+  fn test_empty() {
+    let style = Style {
+      width: 300,
+      ..Style::light()
+    };
+    let canvas = leaderboard(&[], Some(style));
+    assert_eq!((canvas.width, canvas.height), (300, 50 + 64 + 12));
+  }
+}
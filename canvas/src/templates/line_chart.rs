@@ -0,0 +1,247 @@
+use {
+  super::chart::{
+    ChartStyle,
+    ValueScale,
+    XAxis,
+    format_value,
+    widen
+  },
+  crate::{
+    canvas::{
+      Canvas,
+      assume_text_width
+    },
+    layer::{
+      Anchor,
+      Layer
+    },
+    raster::Region
+  },
+  asahi_utils::format_unix_time,
+  image::Rgba
+};
+
+/// Line of the chart, points are drawn in order of their X value
+#[derive(Debug, Clone)]
+pub struct Series {
+  /// Shown in the legend
+  pub name:   String,
+  /// X and Y of each point, X is a unix timestamp in seconds with [XAxis::Time]
+  pub points: Vec<(f64, f64)>,
+  pub color:  Rgba<u8>,
+  /// Shades the area between the line and the bottom of the chart
+  pub fill:   bool
+}
+
+impl Series {
+  pub fn new(
+    name: impl Into<String>,
+    color: Rgba<u8>,
+    points: Vec<(f64, f64)>
+  ) -> Self {
+    Self {
+      name: name.into(),
+      points,
+      color,
+      fill: false
+    }
+  }
+
+  /// Series with the values spread evenly, X being their index
+  pub fn from_values(
+    name: impl Into<String>,
+    color: Rgba<u8>,
+    values: &[f64]
+  ) -> Self {
+    Self::new(name, color, values.iter().enumerate().map(|(i, v)| (i as f64, *v)).collect())
+  }
+
+  pub fn with_fill(mut self) -> Self {
+    self.fill = true;
+    self
+  }
+}
+
+/// Renders the series into a chart of given size
+pub fn line_chart(
+  width: u32,
+  height: u32,
+  series: &[Series],
+  style: Option<ChartStyle>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let mut canvas = Canvas::new(width.max(1), height.max(1));
+  canvas.set_bg_color(style.bg_color);
+  draw_line_chart(&mut canvas, Region::new(0, 0, width, height), series, &style);
+  canvas
+}
+
+/// Draws the chart into the area of an existing canvas, the background is left as is<br>
+/// Parts that don't fit, like labels on a tiny area, are skipped instead of overflowing
+pub fn draw_line_chart(
+  canvas: &mut Canvas,
+  area: Region,
+  series: &[Series],
+  style: &ChartStyle
+) {
+  let font = style.font.to_fontarc();
+  let pad = style.padding as i32;
+  let legend: Vec<_> = series.iter().map(|s| (s.name.as_str(), s.color)).collect();
+  let top = style.draw_header(canvas, area.x + pad, area.y + pad, &legend);
+
+  let points = || {
+    series
+      .iter()
+      .flat_map(|s| s.points.iter())
+      .filter(|(x, y)| x.is_finite() && y.is_finite())
+  };
+  let (x_min, x_max) = points().fold((f64::MAX, f64::MIN), |(lo, hi), (x, _)| (lo.min(*x), hi.max(*x)));
+  let (x_min, x_max) = if x_min > x_max { (0.0, 1.0) } else { widen(x_min, x_max) };
+  let y_axis = ValueScale::new(points().map(|(_, y)| *y), style);
+
+  let y_labels: Vec<String> = y_axis.ticks().iter().map(|v| y_axis.label(*v)).collect();
+  let label_width = if style.show_axes {
+    y_labels.iter().map(|l| assume_text_width(l, style.font_size, &font)).max().unwrap_or(0) as i32 + pad / 2
+  } else {
+    0
+  };
+  let bottom_labels = if style.show_axes { style.line_height() } else { 0 };
+
+  let plot = Region::new(
+    area.x + pad + label_width,
+    top,
+    (area.right() - pad - (area.x + pad + label_width)).max(0) as u32,
+    (area.bottom() - pad - bottom_labels - top).max(0) as u32
+  );
+  if plot.width < 2 || plot.height < 2 {
+    return
+  }
+
+  let to_px = |(x, y): (f64, f64)| -> (i32, i32) {
+    let px = plot.x as f64 + (x - x_min) / (x_max - x_min) * plot.width as f64;
+    let py = plot.bottom() as f64 - y_axis.fraction(y) * plot.height as f64;
+    (px.round() as i32, py.round() as i32)
+  };
+
+  for (value, label) in y_axis.ticks().into_iter().zip(&y_labels) {
+    let y = to_px((x_min, value)).1;
+    if style.show_grid {
+      canvas.add_layer(Layer::Line {
+        start: (plot.x, y),
+        end:   (plot.right(), y),
+        width: 1,
+        color: style.grid_color
+      });
+    }
+    if style.show_axes {
+      canvas.add_layer(style.text(label, (0, 0)).anchored(Anchor::Right, (plot.x - pad / 2, y)));
+    }
+  }
+
+  if style.show_axes {
+    let x_ticks = style.ticks.0.max(2);
+    for i in 0..x_ticks {
+      let value = x_min + (x_max - x_min) * i as f64 / (x_ticks - 1) as f64;
+      let label = match &style.x_axis {
+        XAxis::Values => format_value(value, (x_max - x_min) / (x_ticks - 1) as f64),
+        XAxis::Time(pattern) => format_unix_time(value.round() as i64, pattern)
+      };
+      let anchor = match i {
+        0 => Anchor::TopLeft,
+        i if i == x_ticks - 1 => Anchor::TopRight,
+        _ => Anchor::Top
+      };
+      let x = to_px((value, y_axis.min)).0;
+      canvas.add_layer(style.text(&label, (0, 0)).anchored(anchor, (x, plot.bottom() + 4)));
+    }
+
+    canvas.add_layer(Layer::Line {
+      start: (plot.x, plot.bottom()),
+      end:   (plot.right(), plot.bottom()),
+      width: 1,
+      color: style.axis_color
+    });
+  }
+
+  for s in series {
+    let mut sorted: Vec<_> = s.points.iter().copied().filter(|(x, y)| x.is_finite() && y.is_finite()).collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let pixels: Vec<_> = sorted.into_iter().map(to_px).collect();
+
+    if s.fill && pixels.len() > 1 {
+      let mut outline = pixels.clone();
+      outline.push((pixels[pixels.len() - 1].0, plot.bottom()));
+      outline.push((pixels[0].0, plot.bottom()));
+      canvas.add_layer(Layer::Polygon {
+        points: outline,
+        fill:   style.faded(s.color).into(),
+        border: None
+      });
+    }
+
+    match pixels.as_slice() {
+      [] => {},
+      [point] => canvas.add_layer(Layer::Circle {
+        center: *point,
+        radius: style.line_width.max(2),
+        fill:   s.color.into(),
+        border: None
+      }),
+      _ => {
+        for pair in pixels.windows(2) {
+          canvas.add_layer(Layer::Line {
+            start: pair[0],
+            end:   pair[1],
+            width: style.line_width,
+            color: s.color
+          });
+        }
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot,
+    image::GenericImageView
+  };
+
+  #[test]
+  fn test_snapshot() {
+    let start = 1_735_689_600.0;
+    let hour = |h: f64| start + h * 3600.0;
+    let series = [
+      Series::new(
+        "Players",
+        Rgba([201, 55, 93, 255]),
+        (0..12).map(|h| (hour(h as f64), [2.0, 5.0, 7.0, 10.0, 13.0, 9.0][h % 6])).collect()
+      )
+      .with_fill(),
+      Series::new(
+        "Admins",
+        Rgba([88, 101, 242, 255]),
+        (0..12).map(|h| (hour(h as f64), (h % 3) as f64)).collect()
+      )
+    ];
+    let style = ChartStyle {
+      title: Some("Server activity".to_string()),
+      x_axis: XAxis::Time("%H:%M".to_string()),
+      ..Default::default()
+    };
+
+    assert_snapshot("line_chart", &line_chart(640, 320, &series, Some(style)));
+  }
+
+  #[test]
+  fn test_any_size() {
+    let series = [Series::from_values("Flat", Rgba([255, 0, 0, 255]), &[3.0, 3.0])];
+    for (width, height) in [(0, 0), (1, 1), (10, 400), (400, 10), (48, 48)] {
+      let canvas = line_chart(width, height, &series, None);
+      assert_eq!(canvas.render().dimensions(), (width.max(1), height.max(1)));
+    }
+    assert_eq!(line_chart(200, 100, &[], None).width, 200);
+  }
+}
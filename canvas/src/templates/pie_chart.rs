@@ -0,0 +1,185 @@
+use {
+  super::chart::ChartStyle,
+  crate::{
+    canvas::Canvas,
+    layer::{
+      Anchor,
+      Layer
+    },
+    raster::Region
+  },
+  image::Rgba,
+  std::f32::consts::{
+    FRAC_PI_2,
+    TAU
+  }
+};
+
+/// Slices smaller than this share of the whole don't get a percentage label
+const MIN_LABELLED_SHARE: f64 = 0.04;
+
+/// Most radians between two points of a slice's arc
+const ARC_STEP: f32 = 0.03;
+
+#[derive(Debug, Clone)]
+pub struct Slice {
+  /// Shown in the legend
+  pub label: String,
+  pub value: f64,
+  pub color: Rgba<u8>
+}
+
+impl Slice {
+  pub fn new(
+    label: impl Into<String>,
+    value: f64,
+    color: Rgba<u8>
+  ) -> Self {
+    Self {
+      label: label.into(),
+      value,
+      color
+    }
+  }
+}
+
+/// Renders the slices as a pie with their percentage written on them
+pub fn pie_chart(
+  width: u32,
+  height: u32,
+  slices: &[Slice],
+  style: Option<ChartStyle>
+) -> Canvas {
+  donut_chart(width, height, slices, 0.0, style)
+}
+
+/// Renders the slices as a ring, `hole` being the inner radius relative to the outer one from `0.0` to `0.9`
+pub fn donut_chart(
+  width: u32,
+  height: u32,
+  slices: &[Slice],
+  hole: f32,
+  style: Option<ChartStyle>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let mut canvas = Canvas::new(width.max(1), height.max(1));
+  canvas.set_bg_color(style.bg_color);
+  draw_pie_chart(&mut canvas, Region::new(0, 0, width, height), slices, hole, &style);
+  canvas
+}
+
+/// Draws the pie or donut into the area of an existing canvas, the background is left as is<br>
+/// Slices go clockwise from the top, the ones without a positive value are left out
+pub fn draw_pie_chart(
+  canvas: &mut Canvas,
+  area: Region,
+  slices: &[Slice],
+  hole: f32,
+  style: &ChartStyle
+) {
+  let pad = style.padding as i32;
+  let slices: Vec<_> = slices.iter().filter(|s| s.value.is_finite() && s.value > 0.0).collect();
+  let legend: Vec<_> = slices.iter().map(|s| (s.label.as_str(), s.color)).collect();
+  let top = style.draw_header(canvas, area.x + pad, area.y + pad, &legend);
+
+  let radius = ((area.width as i32 - pad * 2).min(area.bottom() - pad - top) / 2) as f32;
+  let total: f64 = slices.iter().map(|s| s.value).sum();
+  if radius < 2.0 || total <= 0.0 {
+    return
+  }
+  let center = ((area.x + area.width as i32 / 2) as f32, (top + area.bottom() - pad) as f32 / 2.0);
+  let inner = radius * hole.clamp(0.0, 0.9);
+
+  let mut start = -FRAC_PI_2;
+  for slice in &slices {
+    let share = slice.value / total;
+    let end = start + share as f32 * TAU;
+    canvas.add_layer(Layer::Polygon {
+      points: sector(center, radius, inner, start, end),
+      fill:   slice.color.into(),
+      border: None
+    });
+
+    if share >= MIN_LABELLED_SHARE {
+      let middle = (start + end) / 2.0;
+      let distance = if inner > 0.0 { (radius + inner) / 2.0 } else { radius * 0.62 };
+      let at = (center.0 + middle.cos() * distance, center.1 + middle.sin() * distance);
+      canvas.add_layer(
+        Layer::Text {
+          size:     style.font_size,
+          position: (0, 0),
+          color:    style.label_color,
+          content:  format!("{:.0}%", share * 100.0),
+          font:     style.font
+        }
+        .anchored(Anchor::Center, (at.0.round() as i32, at.1.round() as i32))
+      );
+    }
+    start = end;
+  }
+}
+
+/// Outline of the slice between two angles, a ring segment when `inner` isn't zero
+fn sector(
+  center: (f32, f32),
+  outer: f32,
+  inner: f32,
+  start: f32,
+  end: f32
+) -> Vec<(i32, i32)> {
+  let steps = ((end - start) / ARC_STEP).ceil().max(1.0) as usize;
+  let arc = |radius: f32| {
+    (0..=steps).map(move |i| {
+      let angle = start + (end - start) * i as f32 / steps as f32;
+      (
+        (center.0 + angle.cos() * radius).round() as i32,
+        (center.1 + angle.sin() * radius).round() as i32
+      )
+    })
+  };
+
+  let mut points: Vec<_> = arc(outer).collect();
+  if inner > 0.0 {
+    points.extend(arc(inner).rev());
+  } else {
+    points.push((center.0.round() as i32, center.1.round() as i32));
+  }
+  points
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot
+  };
+
+  fn poll() -> [Slice; 4] {
+    [
+      Slice::new("Yes", 48.0, Rgba([87, 242, 135, 255])),
+      Slice::new("No", 27.0, Rgba([237, 66, 69, 255])),
+      Slice::new("Maybe", 22.0, Rgba([254, 231, 92, 255])),
+      Slice::new("Other", 3.0, Rgba([88, 101, 242, 255]))
+    ]
+  }
+
+  #[test]
+  fn test_snapshot_pie() {
+    let style = ChartStyle {
+      title: Some("Poll results".to_string()),
+      ..Default::default()
+    };
+    assert_snapshot("pie_chart", &pie_chart(360, 360, &poll(), Some(style)));
+  }
+
+  #[test]
+  fn test_snapshot_donut() { assert_snapshot("donut_chart", &donut_chart(360, 320, &poll(), 0.55, Some(ChartStyle::light()))); }
+
+  #[test]
+  fn test_degenerate() {
+    let single = [Slice::new("All", 1.0, Rgba([255, 0, 0, 255]))];
+    assert_eq!(pie_chart(100, 100, &single, None).width, 100);
+    assert_eq!(pie_chart(4, 4, &[], None).height, 4);
+    assert!(sector((0.0, 0.0), 10.0, 0.0, 0.0, TAU).len() > 100);
+  }
+}
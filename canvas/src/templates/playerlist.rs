@@ -1,95 +1,288 @@
 use {
-  super::graph::{
-    PlayerGraphOpts,
-    graph_playercount
+  super::{
+    chart::ChartStyle,
+    line_chart::{
+      Series,
+      draw_line_chart
+    },
+    text
   },
   crate::{
+    Region,
     canvas::{
       Canvas,
-      EmoteSource,
       assume_text_width,
-      parse_all_emotes
+      truncate_text
     },
     layer::{
+      Anchor,
       Font as LFont,
       Layer
     },
-    worker::EMOTE_FETCHER_TX
+    rich_text::RichText,
+    transform::ImageTransform
   },
   image::{
     DynamicImage,
     Rgba
-  },
-  std::{
-    collections::HashMap,
-    sync::{
-      LazyLock,
-      Mutex
-    }
   }
 };
 
-/// Caches the fetched images to avoid redownloading them
-pub(crate) static DISCORD_EMOTES_CACHE: LazyLock<Mutex<HashMap<String, DynamicImage>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
-
-/// Player data entry
+/// Player data entry, everything past the name is optional<br>
+/// Build it with [PlayerEntry::new] and the `with_*` methods, struct literals need to end with `..Default::default()`
+#[derive(Default)]
 pub struct PlayerEntry {
-  pub name:     String,
-  pub uptime:   String,
-  pub is_admin: bool,
-  pub emoji:    String
+  pub name:       String,
+  pub uptime:     String,
+  pub is_admin:   bool,
+  pub emoji:      String,
+  /// Drawn as a circle before the name
+  pub avatar:     Option<DynamicImage>,
+  /// Latency in milliseconds, coloured by [Style::ping_thresholds]
+  pub ping:       Option<u32>,
+  /// Colour of the team or role tag at the start of the row
+  pub team_color: Option<Rgba<u8>>,
+  /// ISO 3166 two letter country code, shown as a flag before the name<br>
+  /// Written out as text unless [Style::render_discord_emotes] is on
+  pub country:    Option<String>,
+  pub score:      Option<i64>
 }
 
-/// Style override options
+impl PlayerEntry {
+  pub fn new(
+    name: impl Into<String>,
+    uptime: impl Into<String>
+  ) -> Self {
+    Self {
+      name: name.into(),
+      uptime: uptime.into(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_admin(
+    mut self,
+    is_admin: bool
+  ) -> Self {
+    self.is_admin = is_admin;
+    self
+  }
+
+  pub fn with_emoji(
+    mut self,
+    emoji: impl Into<String>
+  ) -> Self {
+    self.emoji = emoji.into();
+    self
+  }
+
+  pub fn with_avatar(
+    mut self,
+    avatar: DynamicImage
+  ) -> Self {
+    self.avatar = Some(avatar);
+    self
+  }
+
+  pub fn with_ping(
+    mut self,
+    ping: u32
+  ) -> Self {
+    self.ping = Some(ping);
+    self
+  }
+
+  pub fn with_team_color(
+    mut self,
+    color: Rgba<u8>
+  ) -> Self {
+    self.team_color = Some(color);
+    self
+  }
+
+  pub fn with_country(
+    mut self,
+    country: impl Into<String>
+  ) -> Self {
+    self.country = Some(country.into());
+    self
+  }
+
+  pub fn with_score(
+    mut self,
+    score: i64
+  ) -> Self {
+    self.score = Some(score);
+    self
+  }
+}
+
+/// Style override options, start from [Style::dark] (the default) or [Style::light]
 pub struct Style {
   /// Text to display if playerlist is empty<br>
   /// Defaults to **Nobody playing**
   pub playerlist_empty:      String,
-  /// Download and bake Discord emotes into the image<br>
+  /// Text in the header when there are players<br>
+  /// Defaults to **Players online**
+  pub header_text:           String,
+  /// Download and bake the emotes of [PlayerEntry::emoji], emotes in names and country flags into the image<br>
+  /// Names are drawn as plain text when off<br>
   /// Defaults to `false`
   pub render_discord_emotes: bool,
+  /// Most emotes shown on a player's row, the flag comes first, then the name and [PlayerEntry::emoji] get what's left<br>
+  /// Defaults to `3`
+  pub max_emotes:            usize,
   pub bg_color:              Rgba<u8>,
   pub header_bar_color:      Rgba<u8>,
+  /// Background of the even and odd rows
+  pub row_colors:            [Rgba<u8>; 2],
   pub graph_color:           Rgba<u8>,
   pub text_color:            Rgba<u8>,
   pub admin_color:           Rgba<u8>,
   pub font:                  LFont,
   pub font_size:             f32,
+  /// Defaults to `None`, [Style::font_size] plus 8
+  pub header_font_size:      Option<f32>,
+  /// Defaults to `600`
+  pub width:                 u32,
+  /// Defaults to `50`
+  pub header_height:         u32,
+  /// Space below the rows taken by the player graph<br>
+  /// Defaults to `120`
+  pub graph_height:          u32,
   pub row_height:            u32,
-  pub padding:               u32
+  /// Space between the left edge of a column and the player names, avatars and team tags sit in it<br>
+  /// Defaults to `50`
+  pub name_offset:           u32,
+  pub padding:               u32,
+  /// Rows per page, players past them go to the next pages with a **Page 2/4** footer<br>
+  /// Defaults to `None`, one page growing with the player count
+  pub max_rows:              Option<usize>,
+  /// Columns the players are split into, long names are cut to fit their column<br>
+  /// Defaults to `1`
+  pub columns:               u32,
+  /// Pings up to the first value are good and up to the second are fair, anything higher is poor<br>
+  /// Defaults to `(80, 150)`
+  pub ping_thresholds:       (u32, u32),
+  /// Colours of good, fair and poor pings
+  pub ping_colors:           [Rgba<u8>; 3]
 }
 
 impl Default for Style {
   fn default() -> Self {
     Self {
       playerlist_empty:      String::from("Nobody playing"),
+      header_text:           String::from("Players online"),
       render_discord_emotes: false,
+      max_emotes:            3,
       bg_color:              Rgba([5, 5, 5, 255]),
       header_bar_color:      Rgba([10, 10, 10, 255]),
+      row_colors:            [Rgba([10, 10, 10, 255]), Rgba([20, 20, 20, 255])],
       graph_color:           Rgba([201, 55, 93, 255]),
       text_color:            Rgba([255, 255, 255, 255]),
       admin_color:           Rgba([247, 67, 74, 255]),
       font:                  LFont::UbuntuRegular,
       font_size:             24.0,
+      header_font_size:      None,
+      width:                 600,
+      header_height:         50,
+      graph_height:          120,
       row_height:            36,
-      padding:               5
+      name_offset:           50,
+      padding:               5,
+      max_rows:              None,
+      columns:               1,
+      ping_thresholds:       (80, 150),
+      ping_colors:           [Rgba([87, 242, 135, 255]), Rgba([254, 231, 92, 255]), Rgba([237, 66, 69, 255])]
+    }
+  }
+}
+
+impl Style {
+  /// Light text on a near black background
+  pub fn dark() -> Self { Self::default() }
+
+  /// Dark text on a white background
+  pub fn light() -> Self {
+    Self {
+      bg_color: Rgba([255, 255, 255, 255]),
+      header_bar_color: Rgba([240, 240, 240, 255]),
+      row_colors: [Rgba([250, 250, 250, 255]), Rgba([238, 238, 238, 255])],
+      text_color: Rgba([30, 31, 34, 255]),
+      admin_color: Rgba([218, 55, 60, 255]),
+      ping_colors: [Rgba([36, 128, 70, 255]), Rgba([194, 124, 14, 255]), Rgba([218, 55, 60, 255])],
+      ..Default::default()
     }
   }
 }
 
+/// Renders the first page of the playerlist, every player if [Style::max_rows] isn't set
 pub fn playerlist(
   players: &[PlayerEntry],
   graph_data: &[i32],
   display_graph: bool,
   style: Option<Style>
 ) -> Canvas {
+  playerlist_page(players, graph_data, display_graph, 0, style)
+}
+
+/// Renders one page of the playerlist, `page` starts at 0 and is clamped to the last page
+pub fn playerlist_page(
+  players: &[PlayerEntry],
+  graph_data: &[i32],
+  display_graph: bool,
+  page: usize,
+  style: Option<Style>
+) -> Canvas {
+  render_page(players, graph_data, display_graph, page, &style.unwrap_or_default())
+}
+
+/// Renders every page of the playerlist, a single one if [Style::max_rows] isn't set
+pub fn playerlist_pages(
+  players: &[PlayerEntry],
+  graph_data: &[i32],
+  display_graph: bool,
+  style: Option<Style>
+) -> Vec<Canvas> {
   let style = style.unwrap_or_default();
-  let discord_emotes_cache = DISCORD_EMOTES_CACHE.lock().expect("failed to acquire lock");
+  (0..style.page_count(players.len()))
+    .map(|page| render_page(players, graph_data, display_graph, page, &style))
+    .collect()
+}
 
-  let width = 600;
-  let graph_height = if display_graph { 120 } else { 0 };
-  let header_height = 50;
-  let height = header_height + players.len() as u32 * style.row_height + style.padding * 2 + graph_height;
+impl Style {
+  /// Players fitting on one page
+  fn per_page(&self) -> usize { self.max_rows.map_or(usize::MAX, |rows| rows.max(1) * self.columns.max(1) as usize) }
+
+  /// Pages needed to list given number of players
+  pub fn page_count(
+    &self,
+    players: usize
+  ) -> usize {
+    players.div_ceil(self.per_page()).max(1)
+  }
+}
+
+fn render_page(
+  players: &[PlayerEntry],
+  graph_data: &[i32],
+  display_graph: bool,
+  page: usize,
+  style: &Style
+) -> Canvas {
+  let pages = style.page_count(players.len());
+  let page = page.min(pages - 1);
+  let start = page * style.per_page();
+  let shown = &players[start..start.saturating_add(style.per_page()).min(players.len())];
+  let columns = style.columns.max(1);
+  let rows = shown.len().div_ceil(columns as usize) as u32;
+
+  let width = style.width;
+  let graph_height = if display_graph { style.graph_height } else { 0 };
+  let header_height = style.header_height;
+  let footer_height = if pages > 1 { style.row_height } else { 0 };
+  let height = header_height + rows * style.row_height + style.padding * 2 + footer_height + graph_height;
+  let column_width = width / columns;
   let mut canvas = Canvas::new(width, height);
   canvas.set_bg_color(style.bg_color);
 
@@ -97,215 +290,299 @@ pub fn playerlist(
   canvas.add_layer(Layer::Rect {
     size:     (width, header_height),
     position: (0, 0),
-    color:    style.header_bar_color
+    fill:     style.header_bar_color.into()
   });
 
-  // header text
+  // header text, centred on the bar by its font size which puts it 7px from the top with the defaults
+  let header_size = style.header_font_size.unwrap_or(style.font_size + 8.0);
   let content = if players.is_empty() {
-    style.playerlist_empty
+    style.playerlist_empty.clone()
   } else {
-    "Players online".to_string()
+    style.header_text.clone()
   };
-  let fsize = style.font_size + 8.0;
-  let text_width = assume_text_width(&content, fsize, &style.font.to_fontarc());
-  let text_x = (width / 2).saturating_sub(text_width / 2);
-  canvas.add_layer(Layer::Text {
-    size:     fsize,
-    position: (text_x, 7),
-    color:    style.text_color,
-    content:  content.to_string(),
-    font:     style.font
-  });
-
-  if !players.is_empty() {
-    for (i, p) in players.iter().enumerate() {
-      let mut x = 50;
-      let y = header_height + i as u32 * style.row_height;
+  canvas.add_layer(
+    Layer::Text {
+      size:     header_size,
+      position: (0, 0),
+      color:    style.text_color,
+      content:  content.to_string(),
+      font:     style.font
+    }
+    .anchored(Anchor::Top, (width as i32 / 2, ((header_height as f32 - header_size) / 2.0) as i32 - 2))
+  );
+
+  for row in 0..rows {
+    canvas.add_layer(Layer::Rect {
+      size:     (width, style.row_height),
+      position: (0, (header_height + row * style.row_height) as i32),
+      fill:     style.row_colors[row as usize % 2].into()
+    });
+  }
 
-      let alt_color = if i.is_multiple_of(2) {
-        Rgba([10, 10, 10, 255])
-      } else {
-        Rgba([20, 20, 20, 255])
-      };
+  let font = style.font.to_fontarc();
+  let stat_size = style.font_size * 0.85;
+  let ping_width = if shown.iter().any(|p| p.ping.is_some()) {
+    assume_text_width("999 ms", stat_size, &font) + style.padding * 2
+  } else {
+    0
+  };
+  let score_width = shown
+    .iter()
+    .filter_map(|p| p.score)
+    .map(|score| assume_text_width(&score.to_string(), stat_size, &font) + style.padding * 2)
+    .max()
+    .unwrap_or(0);
+
+  // players fill the columns top to bottom, then left to right
+  for (i, p) in shown.iter().enumerate() {
+    let (column, row) = (i as u32 / rows, i as u32 % rows);
+    let column_x = (column * column_width) as i32;
+    let x = column_x + style.name_offset as i32;
+    let y = (header_height + row * style.row_height) as i32;
+    let right = column_x + column_width as i32 - (style.padding * 2) as i32;
+
+    if let Some(color) = p.team_color {
+      canvas.add_layer(Layer::RoundedRect {
+        size:     (4, style.row_height.saturating_sub(12)),
+        position: (column_x + 6, y + 6),
+        radius:   2,
+        fill:     color.into(),
+        border:   None
+      });
+    }
 
-      canvas.add_layer(Layer::Rect {
-        size:     (width, style.row_height),
-        position: (0, y),
-        color:    alt_color
+    if let Some(avatar) = &p.avatar {
+      let size = style.row_height.saturating_sub(10).min(36);
+      canvas.add_layer(Layer::Image {
+        scale:     1.0,
+        position:  (x - size as i32 - 8, y + (style.row_height - size) as i32 / 2),
+        image:     avatar.clone(),
+        transform: ImageTransform::avatar(size)
       });
+    }
 
-      let admin_color = if p.is_admin { style.admin_color } else { style.text_color };
-      let uptime = if p.uptime.is_empty() {
-        "Just joined".to_string()
+    if let Some(ping) = p.ping {
+      let (good, fair) = style.ping_thresholds;
+      let color = style.ping_colors[if ping <= good {
+        0
+      } else if ping <= fair {
+        1
       } else {
-        p.uptime.clone()
-      };
+        2
+      }];
+      canvas.add_layer(text(&format!("{ping} ms"), stat_size, color, style.font).anchored(Anchor::Right, (right, y + style.row_height as i32 / 2)));
+    }
+
+    if let Some(score) = p.score {
+      canvas.add_layer(
+        text(&score.to_string(), stat_size, style.text_color, style.font)
+          .anchored(Anchor::Right, (right - ping_width as i32, y + style.row_height as i32 / 2))
+      );
+    }
 
-      // do player name
+    let admin_color = if p.is_admin { style.admin_color } else { style.text_color };
+    let uptime = if p.uptime.is_empty() {
+      " - Just joined".to_string()
+    } else {
+      format!(" - {}", p.uptime)
+    };
+    let available = column_width.saturating_sub(style.name_offset + style.padding * 2 + ping_width + score_width);
+    let uptime_width = assume_text_width(&uptime, style.font_size, &font);
+
+    // flag and player name cut to what's left of the column, followed by their emotes
+    let name_end;
+    if style.render_discord_emotes {
+      // the flag, the name and then the emoji field share the row's emote limit
+      let rich = |content: String, limit: usize| RichText::new(content, style.font_size, admin_color, style.font).with_max_emotes(limit);
+      let flag = p
+        .country
+        .as_deref()
+        .and_then(flag_emoji)
+        .map(|flag| format!("{flag} "))
+        .unwrap_or_default();
+      let flag = rich(flag, style.max_emotes).lay_out();
+      let name = rich(p.name.clone(), style.max_emotes - flag.emotes()).lay_out();
+      let emotes = if p.emoji.is_empty() { String::new() } else { format!(" {}", p.emoji) };
+      let emotes = rich(emotes, style.max_emotes - flag.emotes() - name.emotes()).lay_out();
+      let name_width = available.saturating_sub(flag.width() + uptime_width + emotes.width());
+      let name = name.truncated(name_width);
+
+      let mut caret = x;
+      for line in [flag, name, emotes] {
+        canvas.add_layer(line.layer((caret, y + 5)));
+        caret += line.width() as i32;
+      }
+      name_end = caret;
+    } else {
+      let country = p
+        .country
+        .as_deref()
+        .filter(|code| flag_emoji(code).is_some())
+        .map(|code| format!("{} ", code.to_ascii_uppercase()))
+        .unwrap_or_default();
+      let name_width = available.saturating_sub(assume_text_width(&country, style.font_size, &font) + uptime_width);
+      let name = format!("{country}{}", truncate_text(&p.name, style.font_size, &font, name_width));
+      name_end = x + assume_text_width(&name, style.font_size, &font) as i32;
       canvas.add_layer(Layer::Text {
         size:     style.font_size,
         position: (x, y + 5),
         color:    admin_color,
-        content:  p.name.clone(),
+        content:  name,
         font:     style.font
       });
+    }
 
-      x += assume_text_width(&p.name, style.font_size, &style.font.to_fontarc());
-
-      // render emotes after name
-      let mut rendered = 0;
-      let mut emoji_x = x;
-
-      if style.render_discord_emotes && !p.emoji.is_empty() {
-        for emoji in parse_all_emotes(&p.emoji) {
-          if rendered >= 3 {
-            break;
-          }
-
-          let img_opt = match emoji {
-            EmoteSource::Discord(ref id) => discord_emotes_cache.get(id).cloned(),
-            EmoteSource::Unicode(ch) => {
-              let key = format!("twemoji_{}", ch as u32);
-              discord_emotes_cache.get(&key).cloned()
-            }
-          };
-
-          // if not in cache, notify worker to fetch it from internet
-          if img_opt.is_none() {
-            let _ = EMOTE_FETCHER_TX.send(emoji.clone());
-          }
-
-          if let Some(img) = img_opt {
-            let base_px = match emoji {
-              EmoteSource::Discord(_) => 96.0,
-              EmoteSource::Unicode(_) => 72.0
-            };
-
-            let emote_scale = style.font_size / 72.0;
-            let emote_y = y + ((style.row_height - (style.font_size as u32)) / 2).saturating_sub(2);
-
-            canvas.add_layer(Layer::Image {
-              image:    img,
-              scale:    emote_scale,
-              position: (emoji_x, emote_y)
-            });
-
-            emoji_x += (emote_scale * base_px) as u32 + 4;
-          } else if let EmoteSource::Unicode(ch) = emoji {
-            canvas.add_layer(Layer::Text {
-              size:     style.font_size,
-              position: (emoji_x, y + 5),
-              color:    admin_color,
-              content:  ch.to_string(),
-              font:     style.font
-            });
-            emoji_x += assume_text_width(&ch.to_string(), style.font_size, &style.font.to_fontarc());
-          }
-
-          rendered += 1;
-        }
-      }
+    // do uptime
+    canvas.add_layer(Layer::Text {
+      size:     style.font_size,
+      position: (name_end, y + 5),
+      color:    admin_color,
+      content:  uptime,
+      font:     style.font
+    });
+  }
 
-      // do uptime
-      canvas.add_layer(Layer::Text {
-        size:     style.font_size,
-        position: (emoji_x, y + 5),
-        color:    admin_color,
-        content:  format!(" - {uptime}"),
+  if pages > 1 {
+    let footer_y = header_height + rows * style.row_height + style.padding;
+    canvas.add_layer(
+      Layer::Text {
+        size:     style.font_size * 0.75,
+        position: (0, 0),
+        color:    style.text_color,
+        content:  format!("Page {}/{pages}", page + 1),
         font:     style.font
-      });
-    }
+      }
+      .anchored(Anchor::TopRight, (width as i32 - style.padding as i32 * 4, footer_y as i32))
+    );
   }
 
   if display_graph {
-    let opts = PlayerGraphOpts {
-      line_color: style.graph_color,
+    let values: Vec<f64> = graph_data.iter().map(|v| *v as f64).collect();
+    let chart = ChartStyle {
+      text_color: style.text_color,
+      font: style.font,
+      line_width: 4,
+      show_axes: false,
+      show_legend: false,
       ..Default::default()
     };
-    graph_playercount(&mut canvas, graph_data, opts);
+    let area = Region::new(0, (height - graph_height) as i32, width, graph_height);
+    draw_line_chart(
+      &mut canvas,
+      area,
+      &[Series::from_values("Players", style.graph_color, &values).with_fill()],
+      &chart
+    );
   }
 
   canvas
 }
 
+/// Turns a two letter country code into its flag emoji made of regional indicators
+fn flag_emoji(code: &str) -> Option<String> {
+  if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+    return None
+  }
+  code
+    .to_ascii_uppercase()
+    .chars()
+    .map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+    .collect()
+}
+
+#[cfg(test)]
 mod test {
-  #[allow(unused_imports)]
   use {
     super::{
-      Canvas,
       PlayerEntry,
-      playerlist
+      Style,
+      playerlist,
+      playerlist_page,
+      playerlist_pages
+    },
+    crate::snapshot::{
+      assert_snapshot,
+      stub_emotes
     },
-    crate::ImageFormat,
     image::{
+      DynamicImage,
       GenericImageView,
-      ImageReader
+      ImageReader,
+      Rgba,
+      RgbaImage
     }
   };
 
   #[test]
-  fn test_disk_emptylist() {
+  fn test_snapshot_empty() {
     let players = [];
     let canvas = playerlist(&players, &[2, 5, 7, 10, 13, 9], true, None);
-    std::fs::write(
-      "playerlistempty_export_test.jpg",
-      canvas.to_bytes(Some(ImageFormat::Jpeg { quality: 100 })).unwrap()
-    )
-    .unwrap();
+    assert_snapshot("playerlist_empty", &canvas);
   }
 
   #[test]
-  fn test_disk() {
+  fn test_snapshot() {
     let players = [
       PlayerEntry {
-        name:     "Nwero".to_string(),
-        uptime:   "4 h".to_string(),
+        name: "Nwero".to_string(),
+        uptime: "4 h".to_string(),
         is_admin: true,
-        emoji:    "<:minyanstare:1277044182527246358> <:minyangy:1277044022430531614>".to_string()
+        emoji: "<:minyanstare:1277044182527246358> <:minyangy:1277044022430531614>".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Test2".to_string(),
-        uptime:   "3 h 51 m".to_string(),
+        name: "Test2".to_string(),
+        uptime: "3 h 51 m".to_string(),
         is_admin: false,
-        emoji:    "<:touchgrass:1007748573552726146> <:evilLookUp:1327357088959299676> <:PIPES:1372876799742312539>".to_string()
+        emoji: "<:touchgrass:1007748573552726146> <:evilLookUp:1327357088959299676> <:PIPES:1372876799742312539>".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Friendly Spider".to_string(),
-        uptime:   "3 h 49 m".to_string(),
+        name: "Friendly Spider".to_string(),
+        uptime: "3 h 49 m".to_string(),
         is_admin: true,
-        emoji:    "🕷️".to_string()
+        emoji: "🕷️".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Test4567890".to_string(),
-        uptime:   "3 h 40 m".to_string(),
+        name: "Test4567890".to_string(),
+        uptime: "3 h 40 m".to_string(),
         is_admin: false,
-        emoji:    ":floppy_disk:".to_string()
+        emoji: ":floppy_disk:".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "TestingTesting".to_string(),
-        uptime:   "3 h".to_string(),
+        name: "TestingTesting".to_string(),
+        uptime: "3 h".to_string(),
         is_admin: false,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Daggerwin".to_string(),
-        uptime:   "2 h 4 m".to_string(),
+        name: "Daggerwin".to_string(),
+        uptime: "2 h 4 m".to_string(),
         is_admin: true,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Annoying Thing".to_string(),
-        uptime:   "2 h".to_string(),
+        name: "Annoying Thing".to_string(),
+        uptime: "2 h".to_string(),
         is_admin: false,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Mr. Pallet".to_string(),
-        uptime:   "".to_string(),
+        name: "Mr. Pallet".to_string(),
+        uptime: "".to_string(),
         is_admin: false,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       }
     ];
+    for player in &players {
+      stub_emotes(&player.emoji);
+    }
 
     let canvas = playerlist(
       &players,
@@ -316,45 +593,179 @@ mod test {
         ..Default::default()
       })
     );
-    std::fs::write(
-      "playerlist_export_test.jpg",
-      canvas.to_bytes(Some(ImageFormat::Jpeg { quality: 100 })).unwrap()
-    )
-    .unwrap();
+    assert_snapshot("playerlist", &canvas);
+  }
+
+  #[test]
+  fn test_pages() {
+    let players: Vec<_> = (1..=9)
+      .map(|i| PlayerEntry {
+        name: if i == 2 {
+          "A rather long player name that never fits".to_string()
+        } else {
+          format!("Player {i}")
+        },
+        uptime: format!("{i} h"),
+        is_admin: i == 1,
+        emoji: String::new(),
+        ..Default::default()
+      })
+      .collect();
+    let style = || {
+      Some(Style {
+        max_rows: Some(3),
+        columns: 2,
+        ..Default::default()
+      })
+    };
+
+    let pages = playerlist_pages(&players, &[2, 5, 7], true, style());
+    assert_eq!(pages.len(), 2);
+    assert!(pages[1].height < pages[0].height);
+    assert_eq!(playerlist_page(&players, &[2, 5, 7], true, 99, style()).height, pages[1].height);
+    assert_snapshot("playerlist_pages", &pages[0]);
+  }
+
+  #[test]
+  fn test_narrow_pages() {
+    let players = [PlayerEntry::new("Nwero", "4 h"), PlayerEntry::new("Test2", "1 h")];
+    let style = Style {
+      width: 60,
+      padding: 40,
+      max_rows: Some(1),
+      ..Default::default()
+    };
+
+    let pages = playerlist_pages(&players, &[], false, Some(style));
+    assert_eq!(pages.len(), 2);
+    assert!(pages.iter().all(|page| page.width == 60));
+  }
+
+  #[test]
+  fn test_player_details() {
+    let avatar = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 160, 255])));
+    let players = [
+      PlayerEntry::new("Nwero", "4 h")
+        .with_avatar(avatar.clone())
+        .with_ping(42)
+        .with_team_color(Rgba([88, 101, 242, 255]))
+        .with_country("jp")
+        .with_score(1250),
+      PlayerEntry::new("Somebody with a long name", "1 h")
+        .with_avatar(avatar)
+        .with_ping(120)
+        .with_team_color(Rgba([237, 66, 69, 255]))
+        .with_score(-30),
+      PlayerEntry::new("Laggy", "").with_ping(400).with_country("nowhere")
+    ];
+    stub_emotes("🇯🇵");
+
+    let style = Style {
+      render_discord_emotes: true,
+      ..Default::default()
+    };
+    assert_snapshot("playerlist_details", &playerlist(&players, &[], false, Some(style)));
+  }
+
+  #[test]
+  fn test_row_emote_limit() {
+    stub_emotes("🇯🇵 <:a:101> <:c:103> <:d:104>");
+    let render = |emoji: &str| {
+      let player = PlayerEntry::new("Nwero <:a:101>", "4 h").with_country("jp").with_emoji(emoji);
+      let style = Style {
+        render_discord_emotes: true,
+        ..Default::default()
+      };
+      playerlist(&[player], &[], false, Some(style)).render()
+    };
+
+    // flag and name take two of the three, so only the first emote of the field is drawn, the space before the dropped one stays
+    assert_eq!(render("<:c:103> <:d:104>").as_bytes(), render("<:c:103> ").as_bytes());
+    assert_ne!(render("<:c:103>").as_bytes(), render("").as_bytes());
+  }
+
+  #[test]
+  fn test_light_theme() {
+    let players = [
+      PlayerEntry {
+        is_admin: true,
+        ping: Some(30),
+        ..PlayerEntry::new("Nwero", "4 h")
+      },
+      PlayerEntry {
+        ping: Some(95),
+        ..PlayerEntry::new("Test2", "3 h 51 m")
+      }
+    ];
+    let style = Style {
+      header_text: "On the server".to_string(),
+      width: 420,
+      header_height: 40,
+      header_font_size: Some(24.0),
+      graph_height: 80,
+      name_offset: 16,
+      font_size: 20.0,
+      row_height: 30,
+      ..Style::light()
+    };
+
+    let canvas = playerlist(&players, &[1, 2, 2, 1], true, Some(style));
+    assert_eq!(canvas.width, 420);
+    assert_snapshot("playerlist_light", &canvas);
+  }
+
+  #[test]
+  fn test_high_dpi() {
+    let players = [PlayerEntry {
+      name: "Nwero".to_string(),
+      uptime: "4 h".to_string(),
+      is_admin: true,
+      emoji: "".to_string(),
+      ..Default::default()
+    }];
+    let canvas = playerlist(&players, &[2, 5, 7], true, None);
+    let (width, height) = (canvas.width, canvas.height);
+
+    assert_eq!(canvas.with_scale(2.0).render().dimensions(), (width * 2, height * 2));
   }
 
   #[test]
   fn test_bytes() {
     let players = [
       PlayerEntry {
-        name:     "Nwero".to_string(),
-        uptime:   "4 h".to_string(),
+        name: "Nwero".to_string(),
+        uptime: "4 h".to_string(),
         is_admin: true,
-        emoji:    "🧃".to_string()
+        emoji: "🧃".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Test2".to_string(),
-        uptime:   "3 h 51 m".to_string(),
+        name: "Test2".to_string(),
+        uptime: "3 h 51 m".to_string(),
         is_admin: false,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Friendly Spider".to_string(),
-        uptime:   "3 h 49 m".to_string(),
+        name: "Friendly Spider".to_string(),
+        uptime: "3 h 49 m".to_string(),
         is_admin: true,
-        emoji:    "🕷️".to_string()
+        emoji: "🕷️".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "Test4567890".to_string(),
-        uptime:   "3 h 40 m".to_string(),
+        name: "Test4567890".to_string(),
+        uptime: "3 h 40 m".to_string(),
         is_admin: false,
-        emoji:    "💾".to_string()
+        emoji: "💾".to_string(),
+        ..Default::default()
       },
       PlayerEntry {
-        name:     "TestingTesting".to_string(),
-        uptime:   "3 h".to_string(),
+        name: "TestingTesting".to_string(),
+        uptime: "3 h".to_string(),
         is_admin: false,
-        emoji:    "".to_string()
+        emoji: "".to_string(),
+        ..Default::default()
       }
     ];
 
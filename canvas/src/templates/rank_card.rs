@@ -0,0 +1,250 @@
+use {
+  super::{
+    leaderboard::progress_bar,
+    text
+  },
+  crate::{
+    canvas::{
+      Canvas,
+      assume_text_width
+    },
+    fill::{
+      Fill,
+      ImageFit
+    },
+    layer::{
+      Anchor,
+      Font as LFont,
+      Layer
+    },
+    rich_text::RichText,
+    transform::ImageTransform
+  },
+  asahi_utils::format_compact,
+  image::{
+    DynamicImage,
+    Rgba
+  }
+};
+
+/// Level progress of one member
+#[derive(Default)]
+pub struct RankCard {
+  pub name:       String,
+  /// Drawn as a circle on the left
+  pub avatar:     Option<DynamicImage>,
+  pub level:      u32,
+  /// Position on the leaderboard, left out if `None`
+  pub rank:       Option<u32>,
+  /// XP gained towards the next level
+  pub xp:         u64,
+  /// XP needed to reach the next level
+  pub xp_needed:  u64,
+  /// Covers the card behind the panel, [Style::bg_color] is used if `None`
+  pub background: Option<DynamicImage>
+}
+
+impl RankCard {
+  pub fn new(
+    name: impl Into<String>,
+    level: u32,
+    xp: u64,
+    xp_needed: u64
+  ) -> Self {
+    Self {
+      name: name.into(),
+      level,
+      xp,
+      xp_needed,
+      ..Default::default()
+    }
+  }
+}
+
+/// Style override options, start from [Style::dark] (the default) or [Style::light]
+pub struct Style {
+  pub bg_color:        Rgba<u8>,
+  /// Translucent panel the details sit on, keeps them readable over a busy background
+  pub panel_color:     Rgba<u8>,
+  /// Colour of the XP bar, level and avatar ring
+  pub accent_color:    Rgba<u8>,
+  pub bar_track_color: Rgba<u8>,
+  pub text_color:      Rgba<u8>,
+  /// Colour of the **RANK**, **LEVEL** and XP labels
+  pub muted_color:     Rgba<u8>,
+  pub font:            LFont,
+  /// Used for the name, rank and level
+  pub bold_font:       LFont,
+  pub font_size:       f32,
+  pub name_font_size:  f32,
+  /// Size of the rank and level numbers
+  pub stat_font_size:  f32,
+  /// Defaults to `800`
+  pub width:           u32,
+  /// Defaults to `240`
+  pub height:          u32,
+  /// Space around the panel and inside it<br>
+  /// Defaults to `24`
+  pub padding:         u32,
+  pub corner_radius:   u32
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Self {
+      bg_color:        Rgba([30, 31, 34, 255]),
+      panel_color:     Rgba([0, 0, 0, 150]),
+      accent_color:    Rgba([201, 55, 93, 255]),
+      bar_track_color: Rgba([255, 255, 255, 40]),
+      text_color:      Rgba([255, 255, 255, 255]),
+      muted_color:     Rgba([181, 186, 193, 255]),
+      font:            LFont::UbuntuRegular,
+      bold_font:       LFont::UbuntuBold,
+      font_size:       20.0,
+      name_font_size:  36.0,
+      stat_font_size:  40.0,
+      width:           800,
+      height:          240,
+      padding:         24,
+      corner_radius:   16
+    }
+  }
+}
+
+impl Style {
+  /// Light text on a dark panel
+  pub fn dark() -> Self { Self::default() }
+
+  /// Dark text on a white panel
+  pub fn light() -> Self {
+    Self {
+      bg_color: Rgba([235, 237, 239, 255]),
+      panel_color: Rgba([255, 255, 255, 200]),
+      bar_track_color: Rgba([0, 0, 0, 30]),
+      text_color: Rgba([30, 31, 34, 255]),
+      muted_color: Rgba([92, 94, 102, 255]),
+      ..Default::default()
+    }
+  }
+}
+
+/// Renders the card with the avatar on the left, the name, rank and level along the top and the XP bar below
+pub fn rank_card(
+  card: &RankCard,
+  style: Option<Style>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let (width, height) = (style.width, style.height);
+  let pad = style.padding as i32;
+  let mut canvas = Canvas::new(width, height);
+  match &card.background {
+    Some(image) => canvas.set_background(Fill::image(image.clone(), ImageFit::Cover)),
+    None => canvas.set_bg_color(style.bg_color)
+  }
+
+  canvas.add_layer(Layer::RoundedRect {
+    size:     (width.saturating_sub(style.padding * 2), height.saturating_sub(style.padding * 2)),
+    position: (pad, pad),
+    radius:   style.corner_radius,
+    fill:     style.panel_color.into(),
+    border:   None
+  });
+
+  // avatar with a ring in the accent colour
+  let avatar_size = height.saturating_sub(style.padding * 4);
+  let mut x = pad * 2;
+  if let Some(avatar) = &card.avatar {
+    let center = (x + avatar_size as i32 / 2, height as i32 / 2);
+    canvas.add_layer(Layer::Circle {
+      center,
+      radius: avatar_size / 2 + 4,
+      fill: style.accent_color.into(),
+      border: Some(4)
+    });
+    canvas.add_layer(Layer::Image {
+      scale:     1.0,
+      position:  (x, pad * 2),
+      image:     avatar.clone(),
+      transform: ImageTransform::avatar(avatar_size)
+    });
+    x += avatar_size as i32 + pad;
+  }
+  let right = width as i32 - pad * 2;
+
+  // rank and level from the right edge, label in front of each number
+  let mut stats = vec![("LEVEL", card.level.to_string(), style.accent_color)];
+  if let Some(rank) = card.rank {
+    stats.push(("RANK", format!("#{rank}"), style.text_color));
+  }
+  let bold = style.bold_font.to_fontarc();
+  let font = style.font.to_fontarc();
+  let stat_baseline = pad * 2 + style.stat_font_size.ceil() as i32;
+  let mut stat_x = right;
+  for (label, value, color) in stats {
+    canvas.add_layer(text(&value, style.stat_font_size, color, style.bold_font).anchored(Anchor::BottomRight, (stat_x, stat_baseline)));
+    stat_x -= assume_text_width(&value, style.stat_font_size, &bold) as i32 + 6;
+    canvas.add_layer(text(label, style.font_size, style.muted_color, style.font).anchored(Anchor::BottomRight, (stat_x, stat_baseline - 4)));
+    stat_x -= assume_text_width(label, style.font_size, &font) as i32 + pad;
+  }
+
+  // name just above the bar, cut short before it reaches the xp counter
+  let bar_height = (height / 10).max(8);
+  let bar_y = height as i32 - pad * 2 - bar_height as i32;
+  let xp = format!("{} / {} XP", format_compact(card.xp as f64), format_compact(card.xp_needed as f64));
+  let xp_width = assume_text_width(&xp, style.font_size, &font) as i32;
+  canvas.add_layer(text(&xp, style.font_size, style.muted_color, style.font).anchored(Anchor::BottomRight, (right, bar_y - 8)));
+
+  let name_width = (right - xp_width - pad - x).max(0) as u32;
+  canvas.add_layer(
+    RichText::new(card.name.as_str(), style.name_font_size, style.text_color, style.bold_font)
+      .lay_out()
+      .truncated(name_width)
+      .layer((0, 0))
+      .anchored(Anchor::BottomLeft, (x, bar_y - 6))
+  );
+
+  let fraction = if card.xp_needed > 0 {
+    card.xp as f32 / card.xp_needed as f32
+  } else {
+    1.0
+  };
+  canvas.add_layer(progress_bar(
+    (x, bar_y),
+    ((right - x).max(0) as u32, bar_height),
+    fraction,
+    style.bar_track_color,
+    style.accent_color
+  ));
+
+  canvas
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot,
+    image::RgbaImage
+  };
+
+  #[test]
+  fn test_snapshot() {
+    let avatar = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 160, 255])));
+    let background = DynamicImage::ImageRgba8(RgbaImage::from_fn(160, 48, |x, y| Rgba([40 + x as u8, 20 + y as u8 * 2, 120, 255])));
+    let card = RankCard {
+      avatar: Some(avatar),
+      rank: Some(3),
+      background: Some(background),
+      ..RankCard::new("Nwero", 24, 1_250, 3_000)
+    };
+
+    assert_snapshot("rank_card", &rank_card(&card, None));
+  }
+
+  #[test]
+  fn test_plain() {
+    let card = RankCard::new("Somebody with a name far too long for the card", 0, 0, 0);
+    let canvas = rank_card(&card, Some(Style::light()));
+    assert_eq!((canvas.width, canvas.height), (800, 240));
+  }
+}
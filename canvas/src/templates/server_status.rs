@@ -0,0 +1,300 @@
+use {
+  super::{
+    chart::ChartStyle,
+    line_chart::{
+      Series,
+      draw_line_chart
+    },
+    text
+  },
+  crate::{
+    Region,
+    canvas::{
+      Canvas,
+      assume_text_width,
+      truncate_text
+    },
+    layer::{
+      Anchor,
+      Font as LFont,
+      Layer
+    },
+    rich_text::RichText,
+    transform::ImageTransform
+  },
+  asahi_utils::format_duration,
+  image::{
+    DynamicImage,
+    Rgba
+  }
+};
+
+/// State of a game server at the time of rendering
+#[derive(Default)]
+pub struct ServerStatus {
+  pub name:        String,
+  pub online:      bool,
+  pub map:         Option<String>,
+  pub players:     u32,
+  pub max_players: u32,
+  /// Seconds since the server started, written with [format_duration]
+  pub uptime:      Option<u64>,
+  /// Player counts over time like the playerlist graph, oldest first
+  pub graph_data:  Vec<i32>,
+  /// Server or game icon drawn before the name
+  pub icon:        Option<DynamicImage>
+}
+
+impl ServerStatus {
+  pub fn new(
+    name: impl Into<String>,
+    online: bool
+  ) -> Self {
+    Self {
+      name: name.into(),
+      online,
+      ..Default::default()
+    }
+  }
+}
+
+/// Style override options, start from [Style::dark] (the default) or [Style::light]
+pub struct Style {
+  pub bg_color:        Rgba<u8>,
+  /// Background of the bottom part holding the player count and graph
+  pub footer_color:    Rgba<u8>,
+  pub text_color:      Rgba<u8>,
+  /// Colour of the map, uptime and labels
+  pub muted_color:     Rgba<u8>,
+  pub online_color:    Rgba<u8>,
+  pub offline_color:   Rgba<u8>,
+  pub graph_color:     Rgba<u8>,
+  /// Text of the badge when online and offline<br>
+  /// Defaults to **ONLINE** and **OFFLINE**
+  pub badge_text:      (String, String),
+  pub font:            LFont,
+  /// Used for the name, badge and player count
+  pub bold_font:       LFont,
+  pub font_size:       f32,
+  pub title_font_size: f32,
+  /// Defaults to `600`
+  pub width:           u32,
+  /// Defaults to `200`
+  pub height:          u32,
+  /// Height of the bottom part with the player count and graph<br>
+  /// Defaults to `80`
+  pub footer_height:   u32,
+  pub padding:         u32
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Self {
+      bg_color:        Rgba([10, 10, 10, 255]),
+      footer_color:    Rgba([20, 20, 20, 255]),
+      text_color:      Rgba([255, 255, 255, 255]),
+      muted_color:     Rgba([148, 155, 164, 255]),
+      online_color:    Rgba([87, 242, 135, 255]),
+      offline_color:   Rgba([237, 66, 69, 255]),
+      graph_color:     Rgba([201, 55, 93, 255]),
+      badge_text:      (String::from("ONLINE"), String::from("OFFLINE")),
+      font:            LFont::UbuntuRegular,
+      bold_font:       LFont::UbuntuBold,
+      font_size:       20.0,
+      title_font_size: 30.0,
+      width:           600,
+      height:          200,
+      footer_height:   80,
+      padding:         16
+    }
+  }
+}
+
+impl Style {
+  /// Light text on a near black background
+  pub fn dark() -> Self { Self::default() }
+
+  /// Dark text on a white background
+  pub fn light() -> Self {
+    Self {
+      bg_color: Rgba([255, 255, 255, 255]),
+      footer_color: Rgba([240, 240, 240, 255]),
+      text_color: Rgba([30, 31, 34, 255]),
+      muted_color: Rgba([92, 94, 102, 255]),
+      online_color: Rgba([36, 128, 70, 255]),
+      offline_color: Rgba([218, 55, 60, 255]),
+      ..Default::default()
+    }
+  }
+}
+
+/// Renders the status card, the player count and graph are greyed out while the server is offline
+pub fn server_status(
+  status: &ServerStatus,
+  style: Option<Style>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let (width, height) = (style.width, style.height);
+  let pad = style.padding as i32;
+  let footer_height = style.footer_height.min(height);
+  let footer_y = (height - footer_height) as i32;
+  let status_color = if status.online { style.online_color } else { style.offline_color };
+  let mut canvas = Canvas::new(width, height);
+  canvas.set_bg_color(style.bg_color);
+
+  // status strip down the left edge
+  canvas.add_layer(Layer::Rect {
+    size:     (6, height),
+    position: (0, 0),
+    fill:     status_color.into()
+  });
+  canvas.add_layer(Layer::Rect {
+    size:     (width.saturating_sub(6), footer_height),
+    position: (6, footer_y),
+    fill:     style.footer_color.into()
+  });
+
+  // online/offline badge, a dot and label in a pill
+  let font = style.font.to_fontarc();
+  let bold = style.bold_font.to_fontarc();
+  let right = width as i32 - pad;
+  let badge_label = if status.online { &style.badge_text.0 } else { &style.badge_text.1 };
+  let badge_size = style.font_size * 0.75;
+  let badge_height = badge_size.ceil() as u32 + 12;
+  let badge_width = assume_text_width(badge_label, badge_size, &bold) + badge_height + 8;
+  let badge_x = right - badge_width as i32;
+  let badge_middle = pad + badge_height as i32 / 2;
+  let mut tint = status_color;
+  tint.0[3] = 50;
+  canvas.add_layer(Layer::RoundedRect {
+    size:     (badge_width, badge_height),
+    position: (badge_x, pad),
+    radius:   badge_height / 2,
+    fill:     tint.into(),
+    border:   None
+  });
+  canvas.add_layer(Layer::Circle {
+    center: (badge_x + badge_height as i32 / 2, badge_middle),
+    radius: badge_height / 5,
+    fill:   status_color.into(),
+    border: None
+  });
+  canvas
+    .add_layer(text(badge_label, badge_size, status_color, style.bold_font).anchored(Anchor::Right, (right - badge_height as i32 / 3, badge_middle)));
+
+  // icon, name and details above the footer
+  let mut x = pad + 6;
+  let icon_size = (footer_y - pad * 2).clamp(0, 64) as u32;
+  if let Some(icon) = &status.icon {
+    canvas.add_layer(Layer::Image {
+      scale:     1.0,
+      position:  (x, pad),
+      image:     icon.clone(),
+      transform: ImageTransform::avatar(icon_size)
+    });
+    x += icon_size as i32 + pad;
+  }
+  let name_width = (badge_x - pad - x).max(0) as u32;
+  canvas.add_layer(
+    RichText::new(status.name.as_str(), style.title_font_size, style.text_color, style.bold_font)
+      .lay_out()
+      .truncated(name_width)
+      .layer((x, pad))
+  );
+
+  let details: Vec<String> = [
+    status.map.as_ref().map(|map| format!("Map: {map}")),
+    status
+      .uptime
+      .filter(|_| status.online)
+      .map(|secs| format!("Uptime: {}", format_duration(secs)))
+  ]
+  .into_iter()
+  .flatten()
+  .collect();
+  let details_width = (right - x).max(0) as u32;
+  canvas.add_layer(
+    text(
+      &truncate_text(&details.join("   "), style.font_size, &font, details_width),
+      style.font_size,
+      style.muted_color,
+      style.font
+    )
+    .anchored(Anchor::TopLeft, (x, pad + style.title_font_size.ceil() as i32 + 8))
+  );
+
+  // player count on the left of the footer, the graph takes the rest
+  let footer_middle = footer_y + footer_height as i32 / 2;
+  let (count, count_color) = if status.online {
+    (format!("{}/{}", status.players, status.max_players), style.text_color)
+  } else {
+    ("-".to_string(), style.muted_color)
+  };
+  let count_size = style.title_font_size;
+  canvas.add_layer(text(&count, count_size, count_color, style.bold_font).anchored(Anchor::BottomLeft, (pad + 6, footer_middle + 4)));
+  canvas.add_layer(text("players", style.font_size * 0.8, style.muted_color, style.font).anchored(Anchor::TopLeft, (pad + 6, footer_middle + 4)));
+
+  let count_width = assume_text_width(&count, count_size, &bold).max(assume_text_width("players", style.font_size * 0.8, &font)) as i32;
+  let graph_x = pad * 2 + 6 + count_width;
+  let graph = Region::new(
+    graph_x,
+    footer_y + pad / 2,
+    (right - graph_x).max(0) as u32,
+    footer_height.saturating_sub(style.padding)
+  );
+  let values: Vec<f64> = status.graph_data.iter().map(|v| *v as f64).collect();
+  let chart = ChartStyle {
+    text_color: style.muted_color,
+    font: style.font,
+    line_width: 2,
+    padding: 0,
+    show_axes: false,
+    show_legend: false,
+    ..Default::default()
+  };
+  let graph_color = if status.online { style.graph_color } else { style.muted_color };
+  draw_line_chart(
+    &mut canvas,
+    graph,
+    &[Series::from_values("Players", graph_color, &values).with_fill()],
+    &chart
+  );
+
+  canvas
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot
+  };
+
+  #[test]
+  fn test_snapshot() {
+    let status = ServerStatus {
+      map: Some("Elmcreek".to_string()),
+      players: 12,
+      max_players: 16,
+      uptime: Some(3 * 86_400 + 5 * 3600 + 42 * 60),
+      graph_data: vec![2, 5, 7, 10, 13, 9, 11, 14, 12],
+      ..ServerStatus::new("Daggerwin's Farming Server", true)
+    };
+    assert_snapshot("server_status", &server_status(&status, None));
+  }
+
+  #[test]
+  fn test_snapshot_offline() {
+    let status = ServerStatus {
+      map: Some("Elmcreek".to_string()),
+      uptime: Some(600),
+      graph_data: vec![4, 3, 1, 0],
+      ..ServerStatus::new("A server with a rather long name that goes on", false)
+    };
+    let style = Style {
+      width: 480,
+      ..Style::light()
+    };
+    assert_snapshot("server_status_offline", &server_status(&status, Some(style)));
+  }
+}
@@ -0,0 +1,118 @@
+use {
+  super::chart::{
+    ChartStyle,
+    widen
+  },
+  crate::{
+    canvas::Canvas,
+    layer::Layer,
+    raster::Region
+  },
+  image::Rgba
+};
+
+/// Renders the values as a bare sparkline of given size
+pub fn sparkline(
+  width: u32,
+  height: u32,
+  values: &[f64],
+  color: Rgba<u8>,
+  style: Option<ChartStyle>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let mut canvas = Canvas::new(width.max(1), height.max(1));
+  canvas.set_bg_color(style.bg_color);
+  canvas.add_layer(sparkline_layer(Region::new(0, 0, width, height), values, color, &style));
+  canvas
+}
+
+/// Line of the values stretched over the area without axes or labels, meant to sit inside other templates<br>
+/// The area under it is shaded with [ChartStyle::fill_opacity] and the last value is marked with a dot
+pub fn sparkline_layer(
+  area: Region,
+  values: &[f64],
+  color: Rgba<u8>,
+  style: &ChartStyle
+) -> Layer {
+  let values: Vec<_> = values.iter().copied().filter(|v| v.is_finite()).collect();
+  let inset = style.line_width as i32 + 1;
+  let inner = Region::new(
+    area.x + inset,
+    area.y + inset,
+    area.width.saturating_sub(inset as u32 * 2),
+    area.height.saturating_sub(inset as u32 * 2)
+  );
+  if values.is_empty() || inner.width == 0 || inner.height == 0 {
+    return Layer::group(Vec::new())
+  }
+
+  let (lo, hi) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+  let (lo, hi) = widen(lo, hi);
+  let last = values.len().saturating_sub(1).max(1) as f64;
+  let points: Vec<_> = values
+    .iter()
+    .enumerate()
+    .map(|(i, v)| {
+      let x = inner.x as f64 + i as f64 / last * inner.width as f64;
+      let y = inner.bottom() as f64 - (v - lo) / (hi - lo) * inner.height as f64;
+      (x.round() as i32, y.round() as i32)
+    })
+    .collect();
+
+  let mut layers = Vec::new();
+  if points.len() > 1 && style.fill_opacity > 0.0 {
+    let mut outline = points.clone();
+    outline.push((points[points.len() - 1].0, area.bottom()));
+    outline.push((points[0].0, area.bottom()));
+    layers.push(Layer::Polygon {
+      points: outline,
+      fill:   style.faded(color).into(),
+      border: None
+    });
+  }
+  for pair in points.windows(2) {
+    layers.push(Layer::Line {
+      start: pair[0],
+      end: pair[1],
+      width: style.line_width,
+      color
+    });
+  }
+  layers.push(Layer::Circle {
+    center: points[points.len() - 1],
+    radius: style.line_width + 1,
+    fill:   color.into(),
+    border: None
+  });
+
+  Layer::group(layers)
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot
+  };
+
+  #[test]
+  fn test_snapshot() {
+    let values = [3.0, 4.0, 2.0, 6.0, 7.0, 5.0, 9.0, 8.0, 11.0, 10.0];
+    let style = ChartStyle {
+      line_width: 2,
+      ..Default::default()
+    };
+    assert_snapshot("sparkline", &sparkline(160, 40, &values, Rgba([87, 242, 135, 255]), Some(style)));
+  }
+
+  #[test]
+  fn test_degenerate() {
+    let style = ChartStyle::default();
+    assert!(matches!(sparkline_layer(Region::new(0, 0, 2, 2), &[1.0, 2.0], Rgba([0; 4]), &style), Layer::Group { layers, .. } if layers.is_empty()));
+    assert!(
+      sparkline_layer(Region::new(0, 0, 50, 20), &[4.0], Rgba([0; 4]), &style)
+        .bounds()
+        .is_some()
+    );
+  }
+}
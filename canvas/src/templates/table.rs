@@ -0,0 +1,417 @@
+use {
+  crate::{
+    Region,
+    canvas::{
+      Canvas,
+      assume_text_width,
+      truncate_text
+    },
+    layer::{
+      Anchor,
+      Font as LFont,
+      Layer
+    },
+    to_rgba
+  },
+  ab_glyph::FontArc,
+  image::Rgba
+};
+
+/// How much room a column takes out of [Style::width]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+  /// Exact width in pixels
+  Fixed(u32),
+  /// Share of the table width from `0.0` to `100.0`
+  Percent(f32),
+  /// Fits the widest cell, room left by the other columns is split between the auto ones<br>
+  /// They shrink by the same ratio if there isn't enough of it
+  Auto
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+  #[default]
+  Left,
+  Center,
+  Right
+}
+
+/// Draws a cell's icon for the row into the given square
+pub type IconFn<T> = Box<dyn Fn(&T, Region) -> Layer>;
+
+/// Column of a table, `T` being the type of its rows
+pub struct Column<T> {
+  pub title:  String,
+  pub width:  ColumnWidth,
+  pub align:  Align,
+  /// Turns the row into the text of its cell
+  pub format: Box<dyn Fn(&T) -> String>,
+  /// Draws something in the square before the text, like a file icon
+  pub icon:   Option<IconFn<T>>
+}
+
+impl<T> Column<T> {
+  /// Left aligned auto-fit column
+  pub fn new(
+    title: impl Into<String>,
+    format: impl Fn(&T) -> String + 'static
+  ) -> Self {
+    Self {
+      title:  title.into(),
+      width:  ColumnWidth::Auto,
+      align:  Align::Left,
+      format: Box::new(format),
+      icon:   None
+    }
+  }
+
+  pub fn with_width(
+    mut self,
+    width: ColumnWidth
+  ) -> Self {
+    self.width = width;
+    self
+  }
+
+  pub fn with_align(
+    mut self,
+    align: Align
+  ) -> Self {
+    self.align = align;
+    self
+  }
+
+  /// Sets the icon drawn in a square of [Style::icon_size] before the text of each cell
+  pub fn with_icon(
+    mut self,
+    icon: impl Fn(&T, Region) -> Layer + 'static
+  ) -> Self {
+    self.icon = Some(Box::new(icon));
+    self
+  }
+}
+
+/// Style override options, start from [Style::dark] (the default) or [Style::light]
+#[derive(Clone)]
+pub struct Style {
+  pub bg_color:             Rgba<u8>,
+  pub header_color:         Rgba<u8>,
+  pub header_text_color:    Rgba<u8>,
+  /// Background of the even and odd rows, the same colour twice turns off the striping
+  pub row_colors:           [Rgba<u8>; 2],
+  pub text_color:           Rgba<u8>,
+  /// Background of the highlighted rows
+  pub highlight_color:      Rgba<u8>,
+  pub highlight_text_color: Rgba<u8>,
+  /// Lines between the column titles, left out if `None`
+  pub separator_color:      Option<Rgba<u8>>,
+  pub font:                 LFont,
+  pub header_font:          LFont,
+  pub font_size:            f32,
+  pub header_font_size:     f32,
+  /// Defaults to `600`
+  pub width:                u32,
+  /// Defaults to `28`
+  pub header_height:        u32,
+  /// Defaults to `24`
+  pub row_height:           u32,
+  /// Space on both sides of the cell contents
+  pub padding:              u32,
+  /// Defaults to `16`
+  pub icon_size:            u32
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Self {
+      bg_color:             to_rgba(0x2B2D31),
+      header_color:         to_rgba(0x1E1F22),
+      header_text_color:    to_rgba(0xB9BBBE),
+      row_colors:           [to_rgba(0x2B2D31), to_rgba(0x232428)],
+      text_color:           to_rgba(0xDBDEE1),
+      highlight_color:      to_rgba(0x404EED),
+      highlight_text_color: to_rgba(0xFFFFFF),
+      separator_color:      Some(to_rgba(0x3F4147)),
+      font:                 LFont::RobotoRegular,
+      header_font:          LFont::RobotoBold,
+      font_size:            12.0,
+      header_font_size:     12.0,
+      width:                600,
+      header_height:        28,
+      row_height:           24,
+      padding:              6,
+      icon_size:            16
+    }
+  }
+}
+
+impl Style {
+  /// Light text on a dark grey background
+  pub fn dark() -> Self { Self::default() }
+
+  /// Black text on a white background
+  pub fn light() -> Self {
+    Self {
+      bg_color: to_rgba(0xFFFFFF),
+      header_color: to_rgba(0xF0F0F0),
+      header_text_color: to_rgba(0x000000),
+      row_colors: [to_rgba(0xFFFFFF), to_rgba(0xF8F8F8)],
+      text_color: to_rgba(0x000000),
+      highlight_color: to_rgba(0x0078D4),
+      separator_color: Some(to_rgba(0xD0D0D0)),
+      ..Default::default()
+    }
+  }
+
+  /// Height of the header and given number of rows
+  pub fn table_height(
+    &self,
+    rows: usize
+  ) -> u32 {
+    self.header_height + rows as u32 * self.row_height
+  }
+}
+
+/// Renders the rows as a table, the rows at `highlighted` indices use [Style::highlight_color]
+pub fn table<T>(
+  rows: &[T],
+  columns: &[Column<T>],
+  highlighted: &[usize],
+  style: Option<Style>
+) -> Canvas {
+  let style = style.unwrap_or_default();
+  let mut canvas = Canvas::new(style.width.max(1), style.table_height(rows.len()).max(1));
+  canvas.set_bg_color(style.bg_color);
+  draw_table(&mut canvas, (0, 0), rows, columns, highlighted, &style);
+  canvas
+}
+
+/// Draws the table into an existing canvas with its top left corner at `position`,
+/// taking [Style::width] by [Style::table_height]
+pub fn draw_table<T>(
+  canvas: &mut Canvas,
+  position: (i32, i32),
+  rows: &[T],
+  columns: &[Column<T>],
+  highlighted: &[usize],
+  style: &Style
+) {
+  let (x, y) = position;
+  let cells: Vec<Vec<String>> = rows.iter().map(|row| columns.iter().map(|c| (c.format)(row)).collect()).collect();
+  let widths = column_widths(columns, &cells, style);
+
+  canvas.add_layer(Layer::Rect {
+    size:     (style.width, style.header_height),
+    position: (x, y),
+    fill:     style.header_color.into()
+  });
+
+  let header_font = style.header_font.to_fontarc();
+  let mut left = x;
+  for (i, (column, width)) in columns.iter().zip(&widths).enumerate() {
+    let cell = Region::new(left, y, *width, style.header_height);
+    canvas.add_layer(cell_text(&column.title, column, cell, true, style.header_text_color, &header_font, style));
+
+    left += *width as i32;
+    if let Some(color) = style.separator_color
+      && i + 1 < columns.len()
+    {
+      canvas.add_layer(Layer::Line {
+        start: (left, y),
+        end: (left, y + style.header_height as i32),
+        width: 1,
+        color
+      });
+    }
+  }
+
+  let font = style.font.to_fontarc();
+  for (i, (row, texts)) in rows.iter().zip(&cells).enumerate() {
+    let row_y = y + style.table_height(i) as i32;
+    let (background, text_color) = if highlighted.contains(&i) {
+      (style.highlight_color, style.highlight_text_color)
+    } else {
+      (style.row_colors[i % 2], style.text_color)
+    };
+    canvas.add_layer(Layer::Rect {
+      size:     (style.width, style.row_height),
+      position: (x, row_y),
+      fill:     background.into()
+    });
+
+    let mut left = x;
+    for ((column, width), text) in columns.iter().zip(&widths).zip(texts) {
+      let cell = Region::new(left, row_y, *width, style.row_height);
+      if let Some(icon) = &column.icon {
+        let size = style.icon_size.min(style.row_height);
+        let square = Region::new(left + style.padding as i32, row_y + (style.row_height - size) as i32 / 2, size, size);
+        canvas.add_layer(icon(row, square));
+      }
+      canvas.add_layer(cell_text(text, column, cell, false, text_color, &font, style));
+      left += *width as i32;
+    }
+  }
+}
+
+/// Text of the cell cut to its width and placed by the column's alignment, after the icon if it has one
+fn cell_text<T>(
+  text: &str,
+  column: &Column<T>,
+  cell: Region,
+  header: bool,
+  color: Rgba<u8>,
+  font: &FontArc,
+  style: &Style
+) -> Layer {
+  let (size, lfont) = if header {
+    (style.header_font_size, style.header_font)
+  } else {
+    (style.font_size, style.font)
+  };
+  let icon = if column.icon.is_some() { style.icon_size + style.padding } else { 0 };
+  let start = cell.x + (style.padding + icon) as i32;
+  let end = cell.right() - style.padding as i32;
+  let content = truncate_text(text, size, font, (end - start).max(0) as u32);
+  let middle = cell.y + cell.height as i32 / 2;
+  let (anchor, at) = match column.align {
+    Align::Left => (Anchor::Left, (start, middle)),
+    Align::Center => (Anchor::Center, ((start + end) / 2, middle)),
+    Align::Right => (Anchor::Right, (end, middle))
+  };
+  Layer::Text {
+    size,
+    position: (0, 0),
+    color,
+    content,
+    font: lfont
+  }
+  .anchored(anchor, at)
+}
+
+/// Pixel width of each column, see [ColumnWidth]
+fn column_widths<T>(
+  columns: &[Column<T>],
+  cells: &[Vec<String>],
+  style: &Style
+) -> Vec<u32> {
+  let font = style.font.to_fontarc();
+  let header_font = style.header_font.to_fontarc();
+  let natural = |i: usize, column: &Column<T>| {
+    let widest = cells
+      .iter()
+      .filter_map(|row| row.get(i))
+      .map(|text| assume_text_width(text, style.font_size, &font))
+      .fold(assume_text_width(&column.title, style.header_font_size, &header_font), u32::max);
+    let icon = if column.icon.is_some() { style.icon_size + style.padding } else { 0 };
+    widest + icon + style.padding * 2
+  };
+
+  let mut widths: Vec<u32> = columns
+    .iter()
+    .enumerate()
+    .map(|(i, column)| match column.width {
+      ColumnWidth::Fixed(width) => width,
+      ColumnWidth::Percent(share) => (style.width as f32 * share.clamp(0.0, 100.0) / 100.0) as u32,
+      ColumnWidth::Auto => natural(i, column)
+    })
+    .collect();
+
+  let auto: Vec<usize> = (0..columns.len()).filter(|i| columns[*i].width == ColumnWidth::Auto).collect();
+  if auto.is_empty() {
+    return widths
+  }
+  let taken: u32 = (0..columns.len()).filter(|i| !auto.contains(i)).map(|i| widths[i]).sum();
+  let room = style.width.saturating_sub(taken);
+  let wanted: u32 = auto.iter().map(|i| widths[*i]).sum();
+
+  if wanted > room {
+    for i in &auto {
+      widths[*i] = (widths[*i] as u64 * room as u64 / wanted.max(1) as u64) as u32;
+    }
+  } else {
+    let extra = (room - wanted) / auto.len() as u32;
+    for i in &auto {
+      widths[*i] += extra;
+    }
+    // rounding leftovers go to the last one so the columns reach the edge
+    widths[auto[auto.len() - 1]] += (room - wanted) % auto.len() as u32;
+  }
+  widths
+}
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::snapshot::assert_snapshot
+  };
+
+  struct Member {
+    name:     &'static str,
+    role:     &'static str,
+    messages: u32
+  }
+
+  fn columns() -> Vec<Column<Member>> {
+    vec![
+      Column::new("Name", |m: &Member| m.name.to_string()).with_icon(|m, area| Layer::Circle {
+        center: (area.x + area.width as i32 / 2, area.y + area.height as i32 / 2),
+        radius: area.width / 2,
+        fill:   if m.role == "Admin" { to_rgba(0xED4245) } else { to_rgba(0x5865F2) }.into(),
+        border: None
+      }),
+      Column::new("Role", |m: &Member| m.role.to_string())
+        .with_width(ColumnWidth::Percent(25.0))
+        .with_align(Align::Center),
+      Column::new("Messages", |m: &Member| m.messages.to_string())
+        .with_width(ColumnWidth::Fixed(110))
+        .with_align(Align::Right),
+    ]
+  }
+
+  fn members() -> [Member; 4] {
+    [
+      Member {
+        name:     "Nwero",
+        role:     "Admin",
+        messages: 15320
+      },
+      Member {
+        name:     "Daggerwin",
+        role:     "Moderator",
+        messages: 12100
+      },
+      Member {
+        name:     "Somebody with a name too long for the column it sits in",
+        role:     "Member",
+        messages: 987
+      },
+      Member {
+        name:     "Mr. Pallet",
+        role:     "Member",
+        messages: 42
+      }
+    ]
+  }
+
+  #[test]
+  fn test_snapshot() {
+    let style = Style {
+      width: 420,
+      ..Default::default()
+    };
+    assert_snapshot("table", &table(&members(), &columns(), &[1], Some(style)));
+  }
+
+  #[test]
+  fn test_column_widths() {
+    let style = Style::light();
+    let cells: Vec<Vec<String>> = members().iter().map(|m| columns().iter().map(|c| (c.format)(m)).collect()).collect();
+    assert_eq!(column_widths(&columns(), &cells, &style), vec![600 - 150 - 110, 150, 110]);
+
+    // auto columns shrink to the room that's left
+    let narrow = Style { width: 200, ..style };
+    assert!(column_widths(&columns(), &cells, &narrow)[0] <= 40);
+  }
+}
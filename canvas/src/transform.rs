@@ -0,0 +1,342 @@
+use {
+  crate::{
+    fill::{
+      ImageFit,
+      fit_image
+    },
+    raster::{
+      ellipse_sdf,
+      rounded_rect_sdf
+    },
+    scene::color
+  },
+  image::{
+    DynamicImage,
+    GenericImageView,
+    Rgba,
+    RgbaImage,
+    imageops::{
+      self,
+      FilterType
+    }
+  },
+  serde::{
+    Deserialize,
+    Serialize
+  },
+  std::hash::{
+    Hash,
+    Hasher
+  }
+};
+
+/// Adjustments applied to an image layer before it's drawn, in this order:
+/// resize, filters, mask and rotation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageTransform {
+  /// Fits the image into a box of this size instead of using the layer's `scale`
+  pub size:     Option<(u32, u32)>,
+  pub fit:      ImageFit,
+  pub mask:     ImageMask,
+  /// Clockwise rotation around the image's center in degrees
+  pub rotation: f32,
+  pub filters:  Vec<ImageFilter>,
+  pub resample: Resample
+}
+
+/// Shape the image is cropped to
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageMask {
+  #[default]
+  None,
+  /// Ellipse inscribed in the image, a circle for square images such as avatars
+  Circle,
+  /// Rounds the corners with given radius
+  Rounded(u32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFilter {
+  Grayscale,
+  /// Gaussian blur with given sigma
+  Blur(f32),
+  /// Adds the value to every color channel, negative values darken
+  Brightness(i32),
+  /// Multiplies the colors with given color, its alpha controls the strength
+  Tint(#[serde(with = "color")] Rgba<u8>)
+}
+
+/// Resampling filter used when resizing, sorted from fastest to best looking
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resample {
+  Nearest,
+  Bilinear,
+  CatmullRom,
+  Gaussian,
+  #[default]
+  Lanczos3
+}
+
+impl From<Resample> for FilterType {
+  fn from(resample: Resample) -> Self {
+    match resample {
+      Resample::Nearest => FilterType::Nearest,
+      Resample::Bilinear => FilterType::Triangle,
+      Resample::CatmullRom => FilterType::CatmullRom,
+      Resample::Gaussian => FilterType::Gaussian,
+      Resample::Lanczos3 => FilterType::Lanczos3
+    }
+  }
+}
+
+impl Hash for ImageTransform {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    (self.size, self.fit, self.mask, self.rotation.to_bits(), &self.filters, self.resample).hash(state);
+  }
+}
+
+impl Hash for ImageFilter {
+  fn hash<H: Hasher>(
+    &self,
+    state: &mut H
+  ) {
+    std::mem::discriminant(self).hash(state);
+    match self {
+      ImageFilter::Grayscale => {},
+      ImageFilter::Blur(sigma) => sigma.to_bits().hash(state),
+      ImageFilter::Brightness(value) => value.hash(state),
+      ImageFilter::Tint(tint) => tint.hash(state)
+    }
+  }
+}
+
+impl ImageTransform {
+  /// Cover-fitted image cropped into a circle, the usual avatar look
+  pub fn avatar(size: u32) -> Self {
+    Self {
+      size: Some((size, size)),
+      fit: ImageFit::Cover,
+      mask: ImageMask::Circle,
+      ..Default::default()
+    }
+  }
+
+  /// Same transform for an image drawn `factor` times larger, sizes, mask radius and blur grow along
+  pub(crate) fn scaled(
+    &self,
+    factor: f32
+  ) -> ImageTransform {
+    let by = |v: u32| (v as f32 * factor).round() as u32;
+    ImageTransform {
+      size: self.size.map(|(w, h)| (by(w), by(h))),
+      mask: match self.mask {
+        ImageMask::Rounded(radius) => ImageMask::Rounded(by(radius)),
+        mask => mask
+      },
+      filters: self
+        .filters
+        .iter()
+        .map(|filter| match filter {
+          ImageFilter::Blur(sigma) => ImageFilter::Blur(sigma * factor),
+          filter => *filter
+        })
+        .collect(),
+      ..self.clone()
+    }
+  }
+
+  /// Produces the final image ready to be composited
+  pub(crate) fn apply(
+    &self,
+    image: &DynamicImage,
+    scale: f32
+  ) -> RgbaImage {
+    let mut out = match self.size {
+      Some(size) => fit_image(image, size, self.fit, 0.0, self.resample.into()),
+      None => {
+        let (w, h) = image.dimensions();
+        let nw = (w as f32 * scale) as u32;
+        let nh = (h as f32 * scale) as u32;
+        image.resize_exact(nw, nh, self.resample.into()).to_rgba8()
+      }
+    };
+
+    for filter in &self.filters {
+      out = filter.apply(out);
+    }
+
+    self.mask.apply(&mut out);
+
+    if self.rotation % 360.0 != 0.0 {
+      out = rotate(&out, self.rotation);
+    }
+
+    out
+  }
+}
+
+impl ImageFilter {
+  fn apply(
+    self,
+    img: RgbaImage
+  ) -> RgbaImage {
+    match self {
+      ImageFilter::Grayscale => {
+        let mut img = img;
+        for px in img.pixels_mut() {
+          let l = (0.299 * px.0[0] as f32 + 0.587 * px.0[1] as f32 + 0.114 * px.0[2] as f32).round() as u8;
+          *px = Rgba([l, l, l, px.0[3]]);
+        }
+        img
+      },
+      ImageFilter::Blur(sigma) if sigma > 0.0 => imageops::blur(&img, sigma),
+      ImageFilter::Blur(_) => img,
+      ImageFilter::Brightness(value) => imageops::colorops::brighten(&img, value),
+      ImageFilter::Tint(tint) => {
+        let mut img = img;
+        let strength = tint.0[3] as f32 / 255.0;
+        for px in img.pixels_mut() {
+          for i in 0..3 {
+            let tinted = px.0[i] as f32 * tint.0[i] as f32 / 255.0;
+            px.0[i] = (px.0[i] as f32 + (tinted - px.0[i] as f32) * strength).round() as u8;
+          }
+        }
+        img
+      }
+    }
+  }
+}
+
+impl ImageMask {
+  fn apply(
+    self,
+    img: &mut RgbaImage
+  ) {
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let sdf: Box<dyn Fn(f32, f32) -> f32> = match self {
+      ImageMask::None => return,
+      ImageMask::Circle => Box::new(move |x, y| ellipse_sdf((x, y), (w / 2.0, h / 2.0), (w / 2.0, h / 2.0))),
+      ImageMask::Rounded(radius) => Box::new(move |x, y| rounded_rect_sdf((x, y), (0.0, 0.0), (w, h), radius as f32))
+    };
+
+    for (x, y, px) in img.enumerate_pixels_mut() {
+      let coverage = (0.5 - sdf(x as f32 + 0.5, y as f32 + 0.5)).clamp(0.0, 1.0);
+      px.0[3] = (px.0[3] as f32 * coverage).round() as u8;
+    }
+  }
+}
+
+/// Rotates the image clockwise, growing it so the corners aren't cut off
+fn rotate(
+  img: &RgbaImage,
+  degrees: f32
+) -> RgbaImage {
+  let (sin, cos) = degrees.to_radians().sin_cos();
+  let (w, h) = (img.width() as f32, img.height() as f32);
+  let nw = (w * cos.abs() + h * sin.abs()).ceil() as u32;
+  let nh = (w * sin.abs() + h * cos.abs()).ceil() as u32;
+  let (cx, cy) = (w / 2.0, h / 2.0);
+  let (ncx, ncy) = (nw as f32 / 2.0, nh as f32 / 2.0);
+
+  RgbaImage::from_fn(nw, nh, |x, y| {
+    // map back into the source image and sample it bilinearly
+    let (dx, dy) = (x as f32 + 0.5 - ncx, y as f32 + 0.5 - ncy);
+    let sx = dx * cos + dy * sin + cx - 0.5;
+    let sy = -dx * sin + dy * cos + cy - 0.5;
+    sample_bilinear(img, sx, sy)
+  })
+}
+
+fn sample_bilinear(
+  img: &RgbaImage,
+  x: f32,
+  y: f32
+) -> Rgba<u8> {
+  let (x0, y0) = (x.floor(), y.floor());
+  let (fx, fy) = (x - x0, y - y0);
+  let fetch = |px: f32, py: f32| {
+    if px < 0.0 || py < 0.0 || px >= img.width() as f32 || py >= img.height() as f32 {
+      [0.0; 4]
+    } else {
+      let p = img.get_pixel(px as u32, py as u32);
+      let a = p.0[3] as f32;
+      // premultiply so transparent edges don't bleed dark fringes
+      [p.0[0] as f32 * a, p.0[1] as f32 * a, p.0[2] as f32 * a, a]
+    }
+  };
+
+  let taps = [
+    (fetch(x0, y0), (1.0 - fx) * (1.0 - fy)),
+    (fetch(x0 + 1.0, y0), fx * (1.0 - fy)),
+    (fetch(x0, y0 + 1.0), (1.0 - fx) * fy),
+    (fetch(x0 + 1.0, y0 + 1.0), fx * fy)
+  ];
+
+  let mut acc = [0.0f32; 4];
+  for (px, weight) in taps {
+    for i in 0..4 {
+      acc[i] += px[i] * weight;
+    }
+  }
+
+  if acc[3] <= 0.0 {
+    return Rgba([0, 0, 0, 0])
+  }
+
+  Rgba([
+    (acc[0] / acc[3]).round() as u8,
+    (acc[1] / acc[3]).round() as u8,
+    (acc[2] / acc[3]).round() as u8,
+    acc[3].round() as u8
+  ])
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn square(size: u32) -> DynamicImage { DynamicImage::ImageRgba8(RgbaImage::from_pixel(size, size, Rgba([200, 100, 50, 255]))) }
+
+  #[test]
+  fn test_avatar_is_circular() {
+    let out = ImageTransform::avatar(64).apply(&square(128), 1.0);
+
+    assert_eq!(out.dimensions(), (64, 64));
+    assert_eq!(out.get_pixel(0, 0).0[3], 0);
+    assert_eq!(out.get_pixel(32, 32).0[3], 255);
+  }
+
+  #[test]
+  fn test_rotation_expands_bounds() {
+    let transform = ImageTransform {
+      rotation: 45.0,
+      ..Default::default()
+    };
+    let out = transform.apply(&square(40), 1.0);
+
+    assert!(out.width() >= 56 && out.height() >= 56);
+    assert_eq!(out.get_pixel(out.width() / 2, out.height() / 2).0[3], 255);
+    assert_eq!(out.get_pixel(0, 0).0[3], 0);
+  }
+
+  #[test]
+  fn test_filters() {
+    let transform = ImageTransform {
+      filters: vec![ImageFilter::Grayscale, ImageFilter::Brightness(-20)],
+      resample: Resample::Nearest,
+      ..Default::default()
+    };
+    let px = *transform.apply(&square(4), 1.0).get_pixel(1, 1);
+
+    assert_eq!(px.0[0], px.0[1]);
+    assert_eq!(px.0[1], px.0[2]);
+    assert_eq!(px.0[0], 104);
+  }
+}
@@ -1,59 +1,283 @@
 use {
   crate::{
-    canvas::{
-      EmoteSource,
-      fetch_discord_emote,
-      fetch_twemoji_emote
-    },
-    templates::DISCORD_EMOTES_CACHE
+    canvas::EmoteSource,
+    emote::{
+      EMOTE_CACHE,
+      decode_emote,
+      emote_provider,
+      is_emote_cached
+    }
+  },
+  asahi_internal::{
+    AsahiError,
+    AsahiResult
   },
-  std::sync::{
-    LazyLock,
-    mpsc::{
-      self,
-      Sender
+  std::{
+    collections::{
+      HashMap,
+      HashSet
+    },
+    sync::{
+      LazyLock,
+      Mutex,
+      mpsc::{
+        self,
+        Sender
+      }
+    },
+    time::{
+      Duration,
+      Instant
     }
+  },
+  tokio::{
+    runtime::Handle,
+    task::JoinSet
   }
 };
 
-pub(crate) static EMOTE_FETCHER_TX: LazyLock<Sender<EmoteSource>> = LazyLock::new(spawn_emote_fetcher);
+/// Background prefetching skips an emote that failed for this long before trying it again
+const RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// Emotes fetched at once by [prefetch_emotes]
+const BACKGROUND_CONCURRENCY: usize = 4;
+
+/// Worker for [prefetch_emotes] calls made outside of a Tokio runtime
+static EMOTE_FETCHER_TX: LazyLock<Sender<EmoteSource>> = LazyLock::new(spawn_emote_fetcher);
 
-/// Notify the background worker to refresh the cache with incoming emotes
+/// Emotes whose last fetch failed, along with when it happened
+static FAILED: LazyLock<Mutex<HashMap<EmoteSource, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Emotes queued or being fetched in the background
+static IN_FLIGHT: LazyLock<Mutex<HashSet<EmoteSource>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Notify the background worker to refresh the cache with incoming emotes<br>
+/// Inside a Tokio runtime the fetches are spawned on it, otherwise a worker thread takes care of them<br>
+/// Emotes that are cached, already queued or failed in the last few minutes are skipped
 pub fn prefetch_emotes<I: IntoIterator<Item = EmoteSource>>(emotes: I) {
-  for emote in emotes {
-    let _ = EMOTE_FETCHER_TX.send(emote);
+  let emotes: Vec<_> = emotes.into_iter().filter(claim).collect();
+  if emotes.is_empty() {
+    return
+  }
+
+  match Handle::try_current() {
+    Ok(handle) => {
+      handle.spawn(fetch_all(emotes, BACKGROUND_CONCURRENCY));
+    },
+    Err(_) => {
+      for emote in emotes {
+        let _ = EMOTE_FETCHER_TX.send(emote);
+      }
+    },
+  }
+}
+
+/// Fetches the emotes that aren't cached yet on the caller's runtime, at most `concurrency` at a time<br>
+/// Returns the outcome of every distinct emote in the order given, emotes that failed before are tried again
+pub async fn prefetch_emotes_await<I: IntoIterator<Item = EmoteSource>>(
+  emotes: I,
+  concurrency: usize
+) -> Vec<(EmoteSource, AsahiResult)> {
+  let mut seen = HashSet::new();
+  let emotes: Vec<_> = emotes.into_iter().filter(|e| seen.insert(e.clone())).collect();
+  let missing: Vec<_> = emotes.iter().filter(|e| !is_emote_cached(e)).cloned().collect();
+
+  let mut fetched: HashMap<_, _> = fetch_all(missing, concurrency).await.into_iter().collect();
+  emotes
+    .into_iter()
+    .map(|emote| {
+      let result = fetched.remove(&emote).unwrap_or(Ok(()));
+      (emote, result)
+    })
+    .collect()
+}
+
+/// Marks the emote as queued if it should be fetched in the background
+fn claim(emote: &EmoteSource) -> bool {
+  if is_emote_cached(emote) {
+    return false
+  }
+  if FAILED
+    .lock()
+    .expect("failed to acquire lock")
+    .get(emote)
+    .is_some_and(|at| at.elapsed() < RETRY_AFTER)
+  {
+    return false
+  }
+  IN_FLIGHT.lock().expect("failed to acquire lock").insert(emote.clone())
+}
+
+async fn fetch_all(
+  emotes: Vec<EmoteSource>,
+  concurrency: usize
+) -> Vec<(EmoteSource, AsahiResult)> {
+  let mut pending = emotes.into_iter();
+  let mut tasks = JoinSet::new();
+  let mut running = HashMap::new();
+  let mut results = Vec::new();
+
+  loop {
+    while tasks.len() < concurrency.max(1) {
+      let Some(emote) = pending.next() else { break };
+      let task = emote.clone();
+      let handle = tasks.spawn(async move { fetch(&task).await });
+      running.insert(handle.id(), emote);
+    }
+
+    let Some(joined) = tasks.join_next_with_id().await else { break };
+    let (id, result) = match joined {
+      Ok((id, result)) => (id, result),
+      Err(e) => (e.id(), Err(AsahiError::Worker(format!("emote fetch task failed: {e}").into())))
+    };
+    if let Some(emote) = running.remove(&id) {
+      results.push((emote, result));
+    }
+  }
+
+  results
+}
+
+/// Takes the emote off [IN_FLIGHT] however its fetch ends, one that never finished (the provider panicked or
+/// the task was dropped) is remembered as failed so it's retried later instead of being skipped for good
+struct FetchGuard<'a> {
+  emote:     &'a EmoteSource,
+  succeeded: bool
+}
+
+impl Drop for FetchGuard<'_> {
+  fn drop(&mut self) {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner()).remove(self.emote);
+    let mut failed = FAILED.lock().unwrap_or_else(|e| e.into_inner());
+    if self.succeeded {
+      failed.remove(self.emote);
+    } else {
+      failed.insert(self.emote.clone(), Instant::now());
+    }
   }
 }
 
+/// Fetches the emote into the cache, remembering whether it failed
+async fn fetch(emote: &EmoteSource) -> AsahiResult {
+  let mut guard = FetchGuard { emote, succeeded: false };
+  let result = match emote_provider().fetch(emote).await {
+    Ok(bytes) => match decode_emote(emote, &bytes) {
+      Some(image) => {
+        EMOTE_CACHE.lock().expect("failed to acquire lock").insert_image(emote, image);
+        Ok(())
+      },
+      None => Err(AsahiError::Parse(format!("failed to decode emote {}", emote.cache_key()).into()))
+    },
+    Err(e) => Err(e)
+  };
+
+  guard.succeeded = result.is_ok();
+  result
+}
+
 fn spawn_emote_fetcher() -> Sender<EmoteSource> {
-  use std::thread;
   let (tx, rx) = mpsc::channel::<EmoteSource>();
 
-  thread::spawn(move || {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    use std::collections::HashSet;
-    let mut seen = HashSet::new();
-    rt.block_on(async move {
-      while let Ok(emote) = rx.recv() {
-        if !seen.insert(emote.clone()) {
-          continue;
-        }
-        match emote {
-          EmoteSource::Discord(ref id) => {
-            if let Some(img) = fetch_discord_emote(id).await {
-              DISCORD_EMOTES_CACHE.lock().unwrap().insert(id.clone(), img);
-            }
-          },
-          EmoteSource::Unicode(ch) => {
-            let key = format!("twemoji_{}", ch as u32);
-            if let Some(img) = fetch_twemoji_emote(ch).await {
-              DISCORD_EMOTES_CACHE.lock().unwrap().insert(key, img);
-            }
-          }
-        }
-      }
-    });
+  std::thread::spawn(move || {
+    let rt = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .expect("failed to build emote fetcher runtime");
+    // each fetch runs as its own task, so a panicking provider doesn't take the worker down with it
+    while let Ok(emote) = rx.recv() {
+      let _ = rt.block_on(rt.spawn(async move { fetch(&emote).await }));
+    }
   });
 
   tx
 }
+
+#[cfg(test)]
+mod test {
+  use {
+    super::*,
+    crate::emote::{
+      EmoteProvider,
+      use_test_provider
+    },
+    async_trait::async_trait,
+    image::{
+      DynamicImage,
+      ImageFormat,
+      RgbaImage
+    },
+    std::{
+      io::Cursor,
+      sync::atomic::{
+        AtomicUsize,
+        Ordering
+      }
+    }
+  };
+
+  /// Fails the first two fetches
+  struct Flaky {
+    calls: AtomicUsize
+  }
+
+  #[async_trait]
+  impl EmoteProvider for Flaky {
+    async fn fetch(
+      &self,
+      emote: &EmoteSource
+    ) -> AsahiResult<Vec<u8>> {
+      if self.calls.fetch_add(1, Ordering::SeqCst) < 2 {
+        return Err(AsahiError::Network(format!("{} timed out", emote.cache_key()).into()))
+      }
+      let mut bytes = Vec::new();
+      DynamicImage::ImageRgba8(RgbaImage::new(4, 4))
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+      Ok(bytes)
+    }
+  }
+
+  struct Panicking;
+
+  #[async_trait]
+  impl EmoteProvider for Panicking {
+    async fn fetch(
+      &self,
+      emote: &EmoteSource
+    ) -> AsahiResult<Vec<u8>> {
+      panic!("provider blew up on {}", emote.cache_key())
+    }
+  }
+
+  #[test]
+  fn test_panicking_provider_releases_emote() {
+    let _provider = use_test_provider(Panicking);
+    let emote = EmoteSource::Discord("900301".into());
+    assert!(claim(&emote));
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+    let results = rt.block_on(fetch_all(vec![emote.clone()], 1));
+    assert!(matches!(results.as_slice(), [(_, Err(AsahiError::Worker(_)))]));
+    assert!(!IN_FLIGHT.lock().unwrap().contains(&emote));
+    assert!(FAILED.lock().unwrap().contains_key(&emote));
+  }
+
+  #[test]
+  fn test_prefetch_await_retries() {
+    let _provider = use_test_provider(Flaky { calls: AtomicUsize::new(0) });
+    let emotes = || [EmoteSource::Discord("900001".into()), EmoteSource::Discord("900002".into())];
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+    let first = rt.block_on(prefetch_emotes_await(emotes(), 2));
+    assert_eq!(first.len(), 2);
+    assert!(first.iter().all(|(_, result)| result.is_err()));
+    assert!(
+      !claim(&emotes()[0]),
+      "recently failed emotes should wait before retrying in the background"
+    );
+
+    let second = rt.block_on(prefetch_emotes_await(emotes(), 2));
+    assert!(second.iter().all(|(_, result)| result.is_ok()));
+    assert!(is_emote_cached(&emotes()[1]));
+  }
+}
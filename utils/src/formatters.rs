@@ -37,3 +37,105 @@ pub fn format_bytes(bytes: u64) -> String {
 
   format!("{bytes:.2} {unit}")
 }
+
+/// Formats unix seconds as a UTC date using `%Y`, `%y`, `%m`, `%d`, `%b`, `%H`, `%M` and `%S`, `%%` writes a `%`<br>
+/// e.g `"%d %b %Y %H:%M"` gives '05 Mar 2025 14:07'
+pub fn format_unix_time(
+  secs: i64,
+  pattern: &str
+) -> String {
+  const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+  let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+  let time = secs.rem_euclid(86400);
+  let mut out = String::with_capacity(pattern.len() + 8);
+  let mut chars = pattern.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      out.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('Y') => out.push_str(&year.to_string()),
+      Some('y') => out.push_str(&format!("{:02}", year.rem_euclid(100))),
+      Some('m') => out.push_str(&format!("{month:02}")),
+      Some('d') => out.push_str(&format!("{day:02}")),
+      Some('b') => out.push_str(MONTHS[month as usize - 1]),
+      Some('H') => out.push_str(&format!("{:02}", time / 3600)),
+      Some('M') => out.push_str(&format!("{:02}", time % 3600 / 60)),
+      Some('S') => out.push_str(&format!("{:02}", time % 60)),
+      Some('%') => out.push('%'),
+      Some(other) => {
+        out.push('%');
+        out.push(other);
+      },
+      None => out.push('%')
+    }
+  }
+
+  out
+}
+
+/// Year, month and day of the days since 1970-01-01 in the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + i64::from(month <= 2);
+
+  (year, month, day)
+}
+
+/// Shortens large numbers with a suffix, e.g '950', '12.3k' or '4.5M'
+pub fn format_compact(value: f64) -> String {
+  let units = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "k")];
+
+  // values that would round up to the next unit, like 999 950, already use it
+  match units.iter().find(|(size, _)| value.abs() >= size * 0.999_95) {
+    Some((size, suffix)) => {
+      let short = format!("{:.1}", value / size);
+      format!("{}{suffix}", short.trim_end_matches(".0"))
+    },
+    None => format!("{}", value.round())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const PATTERN: &str = "%Y-%m-%d %H:%M:%S";
+
+  #[test]
+  fn test_unix_time_epoch() {
+    assert_eq!(format_unix_time(0, PATTERN), "1970-01-01 00:00:00");
+    assert_eq!(format_unix_time(0, "%d %b %y"), "01 Jan 70");
+  }
+
+  #[test]
+  fn test_unix_time_leap_days() {
+    assert_eq!(format_unix_time(951_827_696, PATTERN), "2000-02-29 12:34:56");
+    assert_eq!(format_unix_time(4_107_501_296, PATTERN), "2100-02-28 12:34:56");
+    assert_eq!(format_unix_time(4_107_587_696, PATTERN), "2100-03-01 12:34:56");
+    assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+  }
+
+  #[test]
+  fn test_unix_time_negative() {
+    assert_eq!(format_unix_time(-1, PATTERN), "1969-12-31 23:59:59");
+    assert_eq!(format_unix_time(-2_208_988_800, PATTERN), "1900-01-01 00:00:00");
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+  }
+
+  #[test]
+  fn test_unix_time_escapes() {
+    assert_eq!(format_unix_time(0, "100%% at %H%%"), "100% at 00%");
+    assert_eq!(format_unix_time(0, "%q %"), "%q %");
+  }
+}